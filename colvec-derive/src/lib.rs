@@ -6,49 +6,120 @@ use quote::quote;
 use syn::DeriveInput;
 
 #[cfg(not(test))]
-#[proc_macro_derive(ColVec)]
+#[proc_macro_derive(ColVec, attributes(colvec))]
 pub fn colvec_derive(input:TokenStream)->TokenStream{
 	let input:DeriveInput=syn::parse_macro_input!(input);
 	colvec_derive_inner(input)
 }
 
+#[cfg_attr(test, expect(clippy::useless_conversion))]
 fn colvec_derive_inner(input:DeriveInput)->TokenStream{
+	let storage=match &input.data{
+		syn::Data::Struct(_)=>parse_storage(&input.attrs),
+		_=>reject_attributes(&input.attrs).map(|()|Storage::Contiguous),
+	};
+	let storage=match storage{
+		Ok(storage)=>storage,
+		Err(error)=>return error.to_compile_error().into(),
+	};
 	match input.data{
-		syn::Data::Struct(syn::DataStruct{fields:syn::Fields::Named(fields_named),..})=>derive_struct(input.ident,input.vis,fields_named),
-		_=>unimplemented!("Only structs are supported"),
+		syn::Data::Struct(syn::DataStruct{fields,..})=>derive_struct(input.ident,input.vis,input.generics,fields,storage),
+		syn::Data::Enum(syn::DataEnum{variants,..})=>derive_enum(input.ident,input.vis,input.generics,variants),
+		syn::Data::Union(syn::DataUnion{union_token,..})=>{
+			syn::Error::new(union_token.span,"ColVec cannot be derived for unions, only for structs and enums").to_compile_error().into()
+		},
 	}
 }
 
-fn derive_struct(ident:syn::Ident,vis:syn::Visibility,fields:syn::FieldsNamed)->TokenStream{
+#[cfg_attr(test, expect(clippy::useless_conversion))]
+fn derive_struct(ident:syn::Ident,vis:syn::Visibility,mut generics:syn::Generics,fields:syn::Fields,storage:Storage)->TokenStream{
 	let colvec_ident_string=format!("{ident}ColVec");
 	let colvec_ident=syn::Ident::new(&colvec_ident_string,ident.span());
 
-	let fields_count=fields.named.len();
+	// The struct's generic parameters are carried through to every generated item.
+	strip_defaults(&mut generics);
+	let (impl_generics,ty_generics,where_clause)=generics.split_for_impl();
+	let struct_ty:syn::Type=syn::parse_quote!{#ident #ty_generics};
+	let generic_params=generics.params.iter().collect::<Vec<_>>();
+	let generic_args=generic_args(&generics);
+	let where_preds=where_clause.iter().flat_map(|where_clause|&where_clause.predicates).collect::<Vec<_>>();
+	// the generated items' own generic parameters must not collide with the struct's
+	let a=fresh_ident(&generics,"A");
+	let a2=fresh_ident(&generics,"A2");
+	let b=fresh_ident(&generics,"B");
+	let f=fresh_ident(&generics,"F");
+	let h=fresh_ident(&generics,"H");
+	let i=fresh_ident(&generics,"I");
+	let k=fresh_ident(&generics,"K");
+	let r=fresh_ident(&generics,"R");
+	let lt_a=fresh_lifetime(&generics,"a");
+	let lt_r=fresh_lifetime(&generics,"r");
+	let lt_s=fresh_lifetime(&generics,"s");
+
+	// the views of a row or of the columns are tuple structs when the struct is one
+	let is_tuple=matches!(fields,syn::Fields::Unnamed(_));
+	let fields_count=fields.len();
+	let field_indices=(0..fields_count).collect::<Vec<_>>();
+	let tuple_indices=(0..fields_count).map(syn::Index::from).collect::<Vec<_>>();
+	let field_members=fields.members().collect::<Vec<_>>();
+	let field_types=fields.iter().map(|field|field.ty.clone()).collect::<Vec<_>>();
+	let (column_names,field_vis):(Vec<_>,Vec<_>)=match fields.iter().enumerate().map(|(index,field)|column_name_vis(index,field)).collect::<syn::Result<Vec<_>>>(){
+		Ok(columns)=>columns.into_iter().unzip(),
+		Err(error)=>return error.to_compile_error().into(),
+	};
+	let field_slice_fn_idents=column_names.iter().map(|name|quote::format_ident!("{name}_slice",span=name.span())).collect::<Vec<_>>();
+	let field_slice_mut_fn_idents=column_names.iter().map(|name|quote::format_ident!("{name}_slice_mut",span=name.span())).collect::<Vec<_>>();
+	// a struct with no fields would leave the lifetime of the views unused
+	let (view_marker,marker_init)=if fields_count==0{
+		let marker_member=if is_tuple{quote!{0}}else{quote!{_marker}};
+		(Some(lt_a.clone()),Some(quote!{#marker_member: ::core::marker::PhantomData,}))
+	}else{
+		(None,None)
+	};
+	let view_body=|view_types:Vec<syn::Type>|{
+		let marker=view_marker.as_ref().map(|lt_a|->syn::Type{syn::parse_quote!{::core::marker::PhantomData<&#lt_a ()>}});
+		if is_tuple{
+			quote!{(#(#field_vis #view_types,)* #marker) #where_clause;}
+		}else{
+			let marker=marker.map(|marker|quote!{_marker: #marker,});
+			quote!{#where_clause {
+				#(#field_vis #field_members: #view_types,)*
+				#marker
+			}}
+		}
+	};
+
+	let raw=match storage{
+		Storage::Contiguous=>quote!{::colvec::raw::RawColVec},
+		Storage::PerColumn=>quote!{::colvec::raw::RawColumns},
+	};
+	// a single pointer only describes the columns when they share one allocation
+	let contiguous=storage==Storage::Contiguous;
+
+	let iter_ptrs=quote!{(#(::core::ptr::NonNull<#field_types>,)*)};
+	// the start of every column of `buf`
+	let buf_ptrs=quote!{(#(unsafe { ::core::ptr::NonNull::new_unchecked(buf.field_ptr(#field_indices).cast::<#field_types>()) },)*)};
+
 	#[cfg_attr(not(feature = "std"), expect(unused_mut))]
 	let mut colvec: syn::ItemStruct = syn::parse_quote!{
-		#vis struct #colvec_ident<A: ::colvec::alloc::Allocator>{
-			buf: ::colvec::raw::RawColVec<#fields_count, #ident, A>,
+		#vis struct #colvec_ident<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #where_clause {
+			buf: #raw<#fields_count, #struct_ty, #a>,
 			len: usize,
+			// the column pointers of `buf`, kept up to date so that the ColVec can deref to a ColSlice
+			ptrs: #iter_ptrs,
 		}
 	};
 
 	#[cfg(feature = "std")]
-	match colvec.generics.params.first_mut(){
-		Some(syn::GenericParam::Type(type_param))=>{
-			type_param.eq_token=Some(syn::Token![=](type_param.ident.span()));
-			type_param.default=Some(syn::parse_quote!{::colvec::alloc::Global});
-		},
-		// colvec expression always contains type param
-		_ => unreachable!(),
-	}
+	default_global(&mut colvec.generics);
 
 	#[cfg(feature = "std")]
 	let global = quote! {
-		impl #colvec_ident<::colvec::alloc::Global>{
+		impl #impl_generics #colvec_ident<#(#generic_args,)* ::colvec::alloc::Global> #where_clause {
 			#[inline]
 			#[must_use]
 			pub const fn new() -> Self {
-				Self { buf: ::colvec::raw::RawColVec::new_in(::colvec::alloc::Global), len: 0 }
+				Self::from_buf(#raw::new_in(::colvec::alloc::Global), 0)
 			}
 			#[inline]
 			#[must_use]
@@ -56,59 +127,72 @@ fn derive_struct(ident:syn::Ident,vis:syn::Visibility,fields:syn::FieldsNamed)->
 			pub fn with_capacity(capacity: usize) -> Self {
 				Self::with_capacity_in(capacity, ::colvec::alloc::Global)
 			}
+			#[inline]
+			pub fn try_with_capacity(capacity: usize) -> Result<Self, ::colvec::TryReserveError> {
+				Self::try_with_capacity_in(capacity, ::colvec::alloc::Global)
+			}
+		}
+	};
+	#[cfg(feature = "std")]
+	let global_raw_parts = contiguous.then(||quote! {
+		impl #impl_generics #colvec_ident<#(#generic_args,)* ::colvec::alloc::Global> #where_clause {
 			#[inline]
 			pub unsafe fn from_raw_parts(ptr: *mut u8, length: usize, capacity: usize) -> Self {
 				unsafe { Self::from_raw_parts_in(ptr, length, capacity, ::colvec::alloc::Global) }
 			}
 		}
-	};
+	});
 
 	// this trait smuggles information about the input type into RawColVec and RawColVecInner
-	let fields_types=fields.named.iter().map(|field|field.ty.clone());
 	let struct_info = quote! {
-		impl ::colvec::raw::StructInfo<#fields_count> for #ident{
+		impl #impl_generics ::colvec::fields::StructInfo<#fields_count> for #struct_ty #where_clause {
 			const LAYOUT: ::core::alloc::Layout = unsafe {
 				let size = Self::FIELDS.size();
-				let align = align_of::<#ident>();
+				let align = align_of::<#struct_ty>();
 				::core::alloc::Layout::from_size_align_unchecked(size, align)
 			};
-			const FIELDS: ::colvec::fields::Fields<#fields_count> = ::colvec::fields::Fields::from_sizes([
-				#(size_of::<#fields_types>()),*
+			const FIELDS: ::colvec::fields::Fields<#fields_count> = ::colvec::fields::Fields::from_layouts([
+				#(::core::alloc::Layout::new::<#field_types>()),*
 			]);
 		}
 	};
 
-	let field_indices=0..fields_count;
-	let field_types=fields.named.iter().map(|field|field.ty.clone());
-	let field_idents=fields.named.iter().map(|field|field.ident.as_ref().unwrap().clone());
 	let impls = quote! {
-		impl<A: ::colvec::alloc::Allocator> #colvec_ident<A>{
+		// The cached column pointers opt out of the auto traits, so they are restored
+		// with the same bounds as the buffer.
+		unsafe impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> Send for #colvec_ident<#(#generic_args,)* #a>
+		where #(#where_preds,)*
+			#raw<#fields_count, #struct_ty, #a>: Send,
+		{}
+		unsafe impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> Sync for #colvec_ident<#(#generic_args,)* #a>
+		where #(#where_preds,)*
+			#raw<#fields_count, #struct_ty, #a>: Sync,
+		{}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #colvec_ident<#(#generic_args,)* #a> #where_clause {
 			#[inline]
-			pub const fn new_in(alloc: A) -> Self {
-				Self { buf: ::colvec::raw::RawColVec::new_in(alloc), len: 0 }
+			pub const fn new_in(alloc: #a) -> Self {
+				Self::from_buf(#raw::new_in(alloc), 0)
 			}
 			#[inline]
 			#[track_caller]
-			pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
-				Self { buf: ::colvec::raw::RawColVec::with_capacity_in(capacity, alloc), len: 0 }
+			pub fn with_capacity_in(capacity: usize, alloc: #a) -> Self {
+				Self::from_buf(#raw::with_capacity_in(capacity, alloc), 0)
 			}
 			#[inline]
-			pub unsafe fn from_raw_parts_in(ptr: *mut u8, length: usize, capacity: usize, alloc: A) -> Self {
-				unsafe { Self { buf: ::colvec::raw::RawColVec::from_raw_parts_in(ptr, capacity, alloc), len: length } }
+			pub fn try_with_capacity_in(capacity: usize, alloc: #a) -> Result<Self, ::colvec::TryReserveError> {
+				Ok(Self::from_buf(#raw::try_with_capacity_in(capacity, alloc)?, 0))
 			}
-			#[must_use = "losing the pointer will leak memory"]
-			pub fn into_raw_parts(self) -> (*mut u8, usize, usize) {
-				let mut me = ::core::mem::ManuallyDrop::new(self);
-				(me.as_mut_ptr(), me.len(), me.capacity())
+			#[inline]
+			const fn from_buf(buf: #raw<#fields_count, #struct_ty, #a>, len: usize) -> Self {
+				let ptrs = #buf_ptrs;
+				Self { buf, len, ptrs }
 			}
-			#[must_use = "losing the pointer will leak memory"]
-			pub fn into_raw_parts_with_alloc(self) -> (*mut u8, usize, usize, A) {
-				let mut me = ::core::mem::ManuallyDrop::new(self);
-				let len = me.len();
-				let capacity = me.capacity();
-				let ptr = me.as_mut_ptr();
-				let alloc = unsafe { ::core::ptr::read(me.allocator()) };
-				(ptr, len, capacity, alloc)
+			/// Caches the column pointers again after the buffer may have moved.
+			#[inline]
+			fn sync_ptrs(&mut self) {
+				let buf = &self.buf;
+				self.ptrs = #buf_ptrs;
 			}
 			#[inline]
 			pub const fn capacity(&self) -> usize {
@@ -117,21 +201,45 @@ fn derive_struct(ident:syn::Ident,vis:syn::Visibility,fields:syn::FieldsNamed)->
 			#[track_caller]
 			pub fn reserve(&mut self, additional: usize) {
 				self.buf.reserve(self.len, additional);
+				self.sync_ptrs();
 			}
-			#[inline]
-			const fn as_ptr(&self) -> *const u8 {
-				// We shadow the slice method of the same name to avoid going through
-				// `deref`, which creates an intermediate reference.
-				self.buf.ptr()
+			#[track_caller]
+			pub fn reserve_exact(&mut self, additional: usize) {
+				self.buf.reserve_exact(self.len, additional);
+				self.sync_ptrs();
 			}
-			#[inline]
-			const fn as_mut_ptr(&mut self) -> *mut u8 {
-				// We shadow the slice method of the same name to avoid going through
-				// `deref_mut`, which creates an intermediate reference.
-				self.buf.ptr()
+			pub fn try_reserve(&mut self, additional: usize) -> Result<(), ::colvec::TryReserveError> {
+				let result = self.buf.try_reserve(self.len, additional);
+				self.sync_ptrs();
+				result
+			}
+			pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), ::colvec::TryReserveError> {
+				let result = self.buf.try_reserve_exact(self.len, additional);
+				self.sync_ptrs();
+				result
+			}
+			/// Shrinks the capacity as much as possible, packing the columns together
+			/// before the allocation is shrunk.
+			#[track_caller]
+			pub fn shrink_to_fit(&mut self) {
+				if self.capacity() > self.len {
+					self.buf.shrink_to_fit(self.len, self.len);
+					self.sync_ptrs();
+				}
+			}
+			/// Shrinks the capacity with a lower bound.
+			///
+			/// The capacity will remain at least as large as both the length
+			/// and the supplied value.
+			#[track_caller]
+			pub fn shrink_to(&mut self, min_capacity: usize) {
+				if self.capacity() > min_capacity {
+					self.buf.shrink_to_fit(self.len, ::core::cmp::max(self.len, min_capacity));
+					self.sync_ptrs();
+				}
 			}
 			#[inline]
-			pub fn allocator(&self) -> &A {
+			pub fn allocator(&self) -> &#a {
 				self.buf.allocator()
 			}
 			#[inline]
@@ -140,24 +248,32 @@ fn derive_struct(ident:syn::Ident,vis:syn::Visibility,fields:syn::FieldsNamed)->
 
 				self.len = new_len;
 			}
-			pub fn push(&mut self, value: #ident){
+			pub fn push(&mut self, value: #struct_ty){
 				// Inform codegen that the length does not change across grow_one().
 				let len = self.len;
 				// This will panic or abort if we would allocate > isize::MAX bytes
 				// or if the length increment would overflow for zero-sized types.
 				if len == self.buf.capacity() {
 					self.buf.grow_one();
+					self.sync_ptrs();
 				}
 				unsafe {
-					#(
-						let end = self.as_mut_ptr()
-							.add(self.buf.capacity() * <#ident as ::colvec::raw::StructInfo<#fields_count>>::FIELDS.offset_of(#field_indices))
-							.cast::<#field_types>()
-							.add(len);
-						::core::ptr::write(end, value.#field_idents);
-					)*
+					self.write_row(len, value);
+				}
+				self.len = len + 1;
+			}
+			/// Appends a row, returning it back if the buffer could not grow
+			/// instead of panicking or aborting.
+			pub fn try_push(&mut self, value: #struct_ty) -> Result<(), #struct_ty> {
+				let len = self.len;
+				if len == self.buf.capacity() && self.try_reserve(1).is_err() {
+					return Err(value);
+				}
+				unsafe {
+					self.write_row(len, value);
 				}
 				self.len = len + 1;
+				Ok(())
 			}
 			#[inline]
 			#[track_caller]
@@ -176,14 +292,7 @@ fn derive_struct(ident:syn::Ident,vis:syn::Visibility,fields:syn::FieldsNamed)->
 				self.reserve(count);
 				let len = self.len();
 				unsafe {
-					<#ident as ::colvec::raw::StructInfo<#fields_count>>::FIELDS.move_fields(
-						other.as_ptr(),
-						self.as_mut_ptr(),
-						other.capacity(),
-						self.capacity(),
-						len,
-						count,
-					)
+					self.buf.move_rows(&other.buf, len, count)
 				}
 				self.len += count;
 			}
@@ -194,38 +303,55 @@ fn derive_struct(ident:syn::Ident,vis:syn::Visibility,fields:syn::FieldsNamed)->
 		}
 	};
 
-	let field_indices=0..fields_count;
-	let field_types=fields.named.iter().map(|field|field.ty.clone());
-	let field_slice_fn_idents=fields.named.iter().map(|field|{
-		let ident=field.ident.as_ref().unwrap();
-		let slice_ident=format!("{ident}_slice");
-		syn::Ident::new(&slice_ident,ident.span())
-	});
-	let field_slice_mut_fn_idents=fields.named.iter().map(|field|{
-		let ident=field.ident.as_ref().unwrap();
-		let slice_ident=format!("{ident}_slice_mut");
-		syn::Ident::new(&slice_ident,ident.span())
+	let raw_parts = contiguous.then(||quote! {
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			#[inline]
+			pub unsafe fn from_raw_parts_in(ptr: *mut u8, length: usize, capacity: usize, alloc: #a) -> Self {
+				unsafe { Self::from_buf(#raw::from_raw_parts_in(ptr, capacity, alloc), length) }
+			}
+			#[must_use = "losing the pointer will leak memory"]
+			pub fn into_raw_parts(self) -> (*mut u8, usize, usize) {
+				let mut me = ::core::mem::ManuallyDrop::new(self);
+				(me.as_mut_ptr(), me.len(), me.capacity())
+			}
+			#[must_use = "losing the pointer will leak memory"]
+			pub fn into_raw_parts_with_alloc(self) -> (*mut u8, usize, usize, #a) {
+				let mut me = ::core::mem::ManuallyDrop::new(self);
+				let len = me.len();
+				let capacity = me.capacity();
+				let ptr = me.as_mut_ptr();
+				let alloc = unsafe { ::core::ptr::read(me.allocator()) };
+				(ptr, len, capacity, alloc)
+			}
+			#[inline]
+			const fn as_mut_ptr(&mut self) -> *mut u8 {
+				// We shadow the slice method of the same name to avoid going through
+				// `deref_mut`, which creates an intermediate reference.
+				self.buf.ptr()
+			}
+		}
 	});
+
 	let field_access = quote! {
-		impl<A: ::colvec::alloc::Allocator> #colvec_ident<A>{
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #colvec_ident<#(#generic_args,)* #a> #where_clause {
 			#(
 				#[inline]
-				pub const fn #field_slice_fn_idents(&self) -> &[#field_types] {
+				#[allow(dead_code)]
+				#field_vis const fn #field_slice_fn_idents(&self) -> &[#field_types] {
 					unsafe {
 						::core::slice::from_raw_parts(
-							self.as_ptr()
-								.add(self.buf.capacity() * <#ident as ::colvec::raw::StructInfo<#fields_count>>::FIELDS.offset_of(#field_indices))
+							self.buf.field_ptr(#field_indices)
 								.cast::<#field_types>(),
 							self.len
 						)
 					}
 				}
 				#[inline]
-				pub const fn #field_slice_mut_fn_idents(&mut self) -> &mut [#field_types] {
+				#[allow(dead_code)]
+				#field_vis const fn #field_slice_mut_fn_idents(&mut self) -> &mut [#field_types] {
 					unsafe {
 						::core::slice::from_raw_parts_mut(
-							self.as_mut_ptr()
-								.add(self.buf.capacity() * <#ident as ::colvec::raw::StructInfo<#fields_count>>::FIELDS.offset_of(#field_indices))
+							self.buf.field_ptr(#field_indices)
 								.cast::<#field_types>(),
 							self.len
 						)
@@ -235,20 +361,3327 @@ fn derive_struct(ident:syn::Ident,vis:syn::Visibility,fields:syn::FieldsNamed)->
 		}
 	};
 
-	#[cfg_attr(not(feature = "std"), expect(unused_mut))]
-	let mut output=quote! {
-		#colvec
+	let remove = quote! {
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			/// Reads the row at `index` out of every column.
+			///
+			/// # Safety
+			///
+			/// `index` must be less than `self.len`, and the row must not be used again.
+			#[inline]
+			unsafe fn read_row(&self, index: usize) -> #struct_ty {
+				unsafe {
+					#ident {
+						#(
+							#field_members: ::core::ptr::read(
+								self.buf.field_ptr(#field_indices)
+									.cast::<#field_types>()
+									.add(index)
+							),
+						)*
+					}
+				}
+			}
+			/// Writes `value` into the row at `index` of every column.
+			///
+			/// # Safety
+			///
+			/// `index` must be less than the capacity, and the row must not be initialized.
+			#[inline]
+			unsafe fn write_row(&mut self, index: usize, value: #struct_ty) {
+				unsafe {
+					#(
+						::core::ptr::write(
+							self.buf.field_ptr(#field_indices)
+								.cast::<#field_types>()
+								.add(index),
+							value.#field_members,
+						);
+					)*
+				}
+			}
+			/// Drops `count` rows in place starting at `start`.
+			///
+			/// # Safety
+			///
+			/// The rows must be initialized and must not be used again.
+			#[inline]
+			unsafe fn drop_rows(&mut self, start: usize, count: usize) {
+				// The columns are dropped by a tuple of guards, so if one column panics
+				// while dropping, the remaining columns are still dropped during unwinding.
+				unsafe {
+					let _columns = (
+						#(
+							::colvec::column::DropColumn::new(
+								self.buf.field_ptr(#field_indices).cast::<#field_types>().add(start),
+								count,
+							),
+						)*
+					);
+				}
+			}
+			#[inline]
+			pub fn pop(&mut self) -> Option<#struct_ty> {
+				if self.len == 0 {
+					None
+				} else {
+					unsafe {
+						self.len -= 1;
+						Some(self.read_row(self.len))
+					}
+				}
+			}
+			#[inline]
+			#[track_caller]
+			pub fn swap_remove(&mut self, index: usize) -> #struct_ty {
+				#[cold]
+				#[track_caller]
+				fn assert_failed(index: usize, len: usize) -> ! {
+					panic!("swap_remove index (is {index}) should be < len (is {len})");
+				}
 
-		#struct_info
+				let len = self.len;
+				if index >= len {
+					assert_failed(index, len);
+				}
+				unsafe {
+					// We replace self[index] with the last element. Note that if the
+					// bounds check above succeeds there must be a last element (which
+					// can be self[index] itself).
+					let value = self.read_row(index);
+					self.buf.copy_rows(
+						len - 1,
+						index,
+						1,
+					);
+					self.len = len - 1;
+					value
+				}
+			}
+			#[track_caller]
+			pub fn remove(&mut self, index: usize) -> #struct_ty {
+				#[cold]
+				#[track_caller]
+				fn assert_failed(index: usize, len: usize) -> ! {
+					panic!("removal index (is {index}) should be < len (is {len})");
+				}
 
-		#impls
-		#field_access
+				let len = self.len;
+				if index >= len {
+					assert_failed(index, len);
+				}
+				unsafe {
+					// copy it out, unsafely having a copy of the value on
+					// the stack and in the vector at the same time.
+					let value = self.read_row(index);
+					// Shift everything down to fill in that spot.
+					self.buf.copy_rows(
+						index + 1,
+						index,
+						len - index - 1,
+					);
+					self.len = len - 1;
+					value
+				}
+			}
+			pub fn truncate(&mut self, len: usize) {
+				// This is safe because:
+				//
+				// * the rows passed to `drop_rows` are valid; the `len > self.len`
+				//   case avoids creating an invalid range
+				// * the `len` of the vector is shrunk before calling `drop_rows`,
+				//   such that no value will be dropped twice in case `drop_rows`
+				//   were to panic once (if it panics twice, the program aborts).
+				if len > self.len {
+					return;
+				}
+				let remaining_len = self.len - len;
+				self.len = len;
+				unsafe { self.drop_rows(len, remaining_len) };
+			}
+			#[inline]
+			pub fn clear(&mut self) {
+				let len = self.len;
+				// Set len to 0 before dropping, in case a column panics while dropping.
+				self.len = 0;
+				unsafe { self.drop_rows(0, len) };
+			}
+		}
+	};
+
+	let drain_ident=syn::Ident::new(&format!("{ident}Drain"),ident.span());
+	let splice_ident=syn::Ident::new(&format!("{ident}Splice"),ident.span());
+	let clone_bounds=field_bounds(&field_types,syn::parse_quote!{::core::clone::Clone});
+	let insert = quote! {
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			#[track_caller]
+			pub fn insert(&mut self, index: usize, element: #struct_ty) {
+				#[cold]
+				#[track_caller]
+				fn assert_failed(index: usize, len: usize) -> ! {
+					panic!("insertion index (is {index}) should be <= len (is {len})");
+				}
+
+				let len = self.len;
+				if index > len {
+					assert_failed(index, len);
+				}
+
+				// space for the new element
+				if len == self.buf.capacity() {
+					self.buf.grow_one();
+					self.sync_ptrs();
+				}
+
+				unsafe {
+					if index < len {
+						// Shift everything over to make space. (Duplicating the
+						// `index`th element into two consecutive places.)
+						self.buf.copy_rows(
+							index,
+							index + 1,
+							len - index,
+						);
+					}
+					// Write it in, overwriting the first copy of the `index`th
+					// element.
+					self.write_row(index, element);
+				}
+				self.len = len + 1;
+			}
+			/// Replaces the rows in `range` with the rows of `replace_with`,
+			/// returning an iterator over the removed rows.
+			///
+			/// The columns are shifted once for the whole range. Like `Vec::splice`,
+			/// the replacement happens when the returned iterator is dropped.
+			#[inline]
+			#[track_caller]
+			pub fn splice<#r, #i>(&mut self, range: #r, replace_with: #i) -> #splice_ident<'_ #(, #generic_args)*, #i::IntoIter, #a>
+			where
+				#r: ::core::ops::RangeBounds<usize>,
+				#i: ::core::iter::IntoIterator<Item = #struct_ty>,
+			{
+				#splice_ident {
+					drain: self.drain(range),
+					replace_with: replace_with.into_iter(),
+				}
+			}
+			#[track_caller]
+			pub fn extend_from_within<#r>(&mut self, src: #r)
+			where
+				#r: ::core::ops::RangeBounds<usize>,
+				#(#clone_bounds,)*
+			{
+				let ::core::ops::Range { start, end } = ::colvec::range::range(src, self.len);
+				self.reserve(end - start);
+				for index in start..end {
+					unsafe {
+						// Clone the whole row before writing any of it, so that a panicking
+						// clone drops the fields which were already cloned.
+						let value = #ident {
+							#(
+								#field_members: (*self.buf.field_ptr(#field_indices)
+									.cast::<#field_types>()
+									.add(index)).clone(),
+							)*
+						};
+						self.write_row(self.len, value);
+					}
+					self.len += 1;
+				}
+			}
+		}
+
+		#vis struct #splice_ident<#lt_a, #(#generic_params,)* #i: ::core::iter::Iterator<Item = #struct_ty>, #a: ::colvec::alloc::Allocator> #where_clause {
+			drain: #drain_ident<#lt_a #(, #generic_args)*, #a>,
+			replace_with: #i,
+		}
+
+		impl<#(#generic_params,)* #i: ::core::iter::Iterator<Item = #struct_ty>, #a: ::colvec::alloc::Allocator> ::core::iter::Iterator for #splice_ident<'_ #(, #generic_args)*, #i, #a> #where_clause {
+			type Item = #struct_ty;
+			#[inline]
+			fn next(&mut self) -> Option<#struct_ty> {
+				self.drain.next()
+			}
+			#[inline]
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				self.drain.size_hint()
+			}
+		}
+
+		impl<#(#generic_params,)* #i: ::core::iter::Iterator<Item = #struct_ty>, #a: ::colvec::alloc::Allocator> ::core::iter::DoubleEndedIterator for #splice_ident<'_ #(, #generic_args)*, #i, #a> #where_clause {
+			#[inline]
+			fn next_back(&mut self) -> Option<#struct_ty> {
+				self.drain.next_back()
+			}
+		}
+
+		impl<#(#generic_params,)* #i: ::core::iter::Iterator<Item = #struct_ty>, #a: ::colvec::alloc::Allocator> ::core::iter::ExactSizeIterator for #splice_ident<'_ #(, #generic_args)*, #i, #a> #where_clause {}
+
+		impl<#(#generic_params,)* #i: ::core::iter::Iterator<Item = #struct_ty>, #a: ::colvec::alloc::Allocator> Drop for #splice_ident<'_ #(, #generic_args)*, #i, #a> #where_clause {
+			fn drop(&mut self) {
+				self.drain.by_ref().for_each(drop);
+
+				// fill the gap left by the removed rows
+				let vec = &mut *self.drain.vec;
+				let mut filled = vec.len;
+				while filled < self.drain.tail_start {
+					let Some(value) = self.replace_with.next() else {
+						// the replacement ran out, Drain closes the gap
+						return;
+					};
+					unsafe { vec.write_row(filled, value) };
+					filled += 1;
+					vec.len = filled;
+				}
+
+				// the gap is filled, take the tail back from Drain
+				let tail_start = self.drain.tail_start;
+				let tail_end = tail_start + self.drain.tail_len;
+				vec.len = tail_end;
+				self.drain.tail_len = 0;
+
+				// push the remaining rows after the tail, then rotate them in front of it
+				vec.reserve(self.replace_with.size_hint().0);
+				for value in self.replace_with.by_ref() {
+					vec.push(value);
+				}
+				if vec.len == tail_end {
+					return;
+				}
+				unsafe {
+					vec.buf.rotate_rows(
+						tail_start,
+						tail_end,
+						vec.len,
+					);
+				}
+			}
+		}
+	};
+
+	let ref_ident=syn::Ident::new(&format!("{ident}Ref"),ident.span());
+	let mut_ident=syn::Ident::new(&format!("{ident}Mut"),ident.span());
+	let ref_body=view_body(field_types.iter().map(|ty|syn::parse_quote!{&#lt_a #ty}).collect());
+	let mut_body=view_body(field_types.iter().map(|ty|syn::parse_quote!{&#lt_a mut #ty}).collect());
+	let row_refs = quote! {
+		/// A borrowed row, with a shared reference to each field.
+		#[allow(dead_code)]
+		#vis struct #ref_ident<#lt_a, #(#generic_params,)*> #ref_body
+
+		// not derived, which would require the struct's type parameters to be Clone
+		impl #impl_generics ::core::clone::Clone for #ref_ident<'_ #(, #generic_args)*> #where_clause {
+			#[inline]
+			fn clone(&self) -> Self {
+				*self
+			}
+		}
+		impl #impl_generics ::core::marker::Copy for #ref_ident<'_ #(, #generic_args)*> #where_clause {}
+
+		/// A borrowed row, with a mutable reference to each field.
+		#[allow(dead_code)]
+		#vis struct #mut_ident<#lt_a, #(#generic_params,)*> #mut_body
+	};
+
+	let row_access = quote! {
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			#[inline]
+			pub fn get(&self, index: usize) -> Option<#ref_ident<'_ #(, #generic_args)*>> {
+				if index < self.len {
+					Some(unsafe { self.get_unchecked(index) })
+				} else {
+					None
+				}
+			}
+			#[inline]
+			pub fn get_mut(&mut self, index: usize) -> Option<#mut_ident<'_ #(, #generic_args)*>> {
+				if index < self.len {
+					Some(unsafe { self.get_unchecked_mut(index) })
+				} else {
+					None
+				}
+			}
+			#[inline]
+			pub fn first(&self) -> Option<#ref_ident<'_ #(, #generic_args)*>> {
+				self.get(0)
+			}
+			#[inline]
+			pub fn first_mut(&mut self) -> Option<#mut_ident<'_ #(, #generic_args)*>> {
+				self.get_mut(0)
+			}
+			#[inline]
+			pub fn last(&self) -> Option<#ref_ident<'_ #(, #generic_args)*>> {
+				self.get(self.len.wrapping_sub(1))
+			}
+			#[inline]
+			pub fn last_mut(&mut self) -> Option<#mut_ident<'_ #(, #generic_args)*>> {
+				self.get_mut(self.len.wrapping_sub(1))
+			}
+			/// # Safety
+			///
+			/// `index` must be less than `self.len()`.
+			#[inline]
+			pub unsafe fn get_unchecked(&self, index: usize) -> #ref_ident<'_ #(, #generic_args)*> {
+				debug_assert!(index < self.len);
+				unsafe {
+					#ref_ident {
+						#(
+							#field_members: &*self.buf.field_ptr(#field_indices)
+								.cast::<#field_types>()
+								.add(index),
+						)*
+						#marker_init
+					}
+				}
+			}
+			/// # Safety
+			///
+			/// `index` must be less than `self.len()`.
+			#[inline]
+			pub unsafe fn get_unchecked_mut(&mut self, index: usize) -> #mut_ident<'_ #(, #generic_args)*> {
+				debug_assert!(index < self.len);
+				unsafe {
+					#mut_ident {
+						#(
+							#field_members: &mut *self.buf.field_ptr(#field_indices)
+								.cast::<#field_types>()
+								.add(index),
+						)*
+						#marker_init
+					}
+				}
+			}
+		}
 	};
 
+	let iter_ident=syn::Ident::new(&format!("{ident}Iter"),ident.span());
+	let iter_mut_ident=syn::Ident::new(&format!("{ident}IterMut"),ident.span());
+	let into_iter_ident=syn::Ident::new(&format!("{ident}IntoIter"),ident.span());
+	let field_types_tuple=quote!{(#(#field_types,)*)};
+	// build a row view out of the column pointers in `ptrs` at `index`
+	let ptrs_ref=quote!{#ref_ident{
+		#(#field_members: ptrs.#tuple_indices.add(index).as_ref(),)*
+		#marker_init
+	}};
+	let ptrs_mut=quote!{#mut_ident{
+		#(#field_members: ptrs.#tuple_indices.add(index).as_mut(),)*
+		#marker_init
+	}};
+	let sync_bounds=field_bounds(&field_types,syn::parse_quote!{::core::marker::Sync});
+	let send_bounds=field_bounds(&field_types,syn::parse_quote!{::core::marker::Send});
+	#[cfg_attr(not(feature = "std"), expect(unused_mut))]
+	let mut into_iter: syn::ItemStruct = syn::parse_quote!{
+		#vis struct #into_iter_ident<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #where_clause {
+			buf: #raw<#fields_count, #struct_ty, #a>,
+			start: usize,
+			end: usize,
+		}
+	};
 	#[cfg(feature = "std")]
-	output.extend(global);
+	default_global(&mut into_iter.generics);
+	let iter = quote! {
+		/// An iterator over borrowed rows.
+		#vis struct #iter_ident<#lt_a, #(#generic_params,)*> #where_clause {
+			ptrs: #iter_ptrs,
+			start: usize,
+			end: usize,
+			_marker: ::core::marker::PhantomData<&#lt_a #field_types_tuple>,
+		}
 
-	output.into()
+		/// An iterator over mutably borrowed rows.
+		#vis struct #iter_mut_ident<#lt_a, #(#generic_params,)*> #where_clause {
+			ptrs: #iter_ptrs,
+			start: usize,
+			end: usize,
+			_marker: ::core::marker::PhantomData<&#lt_a mut #field_types_tuple>,
+		}
+
+		// The raw column pointers opt out of the auto traits, so they are restored with the
+		// same bounds as the references which the iterators hand out.
+		unsafe impl #impl_generics Send for #iter_ident<'_ #(, #generic_args)*> where #(#where_preds,)* #(#sync_bounds,)* {}
+		unsafe impl #impl_generics Sync for #iter_ident<'_ #(, #generic_args)*> where #(#where_preds,)* #(#sync_bounds,)* {}
+		unsafe impl #impl_generics Send for #iter_mut_ident<'_ #(, #generic_args)*> where #(#where_preds,)* #(#send_bounds,)* {}
+		unsafe impl #impl_generics Sync for #iter_mut_ident<'_ #(, #generic_args)*> where #(#where_preds,)* #(#sync_bounds,)* {}
+
+		impl #impl_generics Clone for #iter_ident<'_ #(, #generic_args)*> #where_clause {
+			#[inline]
+			fn clone(&self) -> Self {
+				Self { ..*self }
+			}
+		}
+
+		impl<#lt_a, #(#generic_params,)*> ::core::iter::Iterator for #iter_ident<#lt_a #(, #generic_args)*> #where_clause {
+			type Item = #ref_ident<#lt_a #(, #generic_args)*>;
+			#[inline]
+			fn next(&mut self) -> Option<#ref_ident<#lt_a #(, #generic_args)*>> {
+				if self.start == self.end {
+					None
+				} else {
+					let index = self.start;
+					self.start += 1;
+					Some(unsafe { let ptrs = self.ptrs; #ptrs_ref })
+				}
+			}
+			#[inline]
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let len = self.end - self.start;
+				(len, Some(len))
+			}
+		}
+		impl<#lt_a, #(#generic_params,)*> ::core::iter::DoubleEndedIterator for #iter_ident<#lt_a #(, #generic_args)*> #where_clause {
+			#[inline]
+			fn next_back(&mut self) -> Option<#ref_ident<#lt_a #(, #generic_args)*>> {
+				if self.start == self.end {
+					None
+				} else {
+					self.end -= 1;
+					let index = self.end;
+					Some(unsafe { let ptrs = self.ptrs; #ptrs_ref })
+				}
+			}
+		}
+		impl #impl_generics ::core::iter::ExactSizeIterator for #iter_ident<'_ #(, #generic_args)*> #where_clause {}
+		impl #impl_generics ::core::iter::FusedIterator for #iter_ident<'_ #(, #generic_args)*> #where_clause {}
+
+		impl<#lt_a, #(#generic_params,)*> ::core::iter::Iterator for #iter_mut_ident<#lt_a #(, #generic_args)*> #where_clause {
+			type Item = #mut_ident<#lt_a #(, #generic_args)*>;
+			#[inline]
+			fn next(&mut self) -> Option<#mut_ident<#lt_a #(, #generic_args)*>> {
+				if self.start == self.end {
+					None
+				} else {
+					let index = self.start;
+					self.start += 1;
+					Some(unsafe { let ptrs = self.ptrs; #ptrs_mut })
+				}
+			}
+			#[inline]
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let len = self.end - self.start;
+				(len, Some(len))
+			}
+		}
+		impl<#lt_a, #(#generic_params,)*> ::core::iter::DoubleEndedIterator for #iter_mut_ident<#lt_a #(, #generic_args)*> #where_clause {
+			#[inline]
+			fn next_back(&mut self) -> Option<#mut_ident<#lt_a #(, #generic_args)*>> {
+				if self.start == self.end {
+					None
+				} else {
+					self.end -= 1;
+					let index = self.end;
+					Some(unsafe { let ptrs = self.ptrs; #ptrs_mut })
+				}
+			}
+		}
+		impl #impl_generics ::core::iter::ExactSizeIterator for #iter_mut_ident<'_ #(, #generic_args)*> #where_clause {}
+		impl #impl_generics ::core::iter::FusedIterator for #iter_mut_ident<'_ #(, #generic_args)*> #where_clause {}
+	};
+
+	let colvec_iter = quote! {
+		/// An iterator that moves rows out of a ColVec.
+		#into_iter
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			/// Gets a pointer to the start of every column.
+			#[inline]
+			#[allow(clippy::unused_unit)]
+			const fn column_ptrs(&self) -> #iter_ptrs {
+				self.ptrs
+			}
+			#[inline]
+			pub fn iter(&self) -> #iter_ident<'_ #(, #generic_args)*> {
+				#iter_ident {
+					ptrs: self.column_ptrs(),
+					start: 0,
+					end: self.len,
+					_marker: ::core::marker::PhantomData,
+				}
+			}
+			#[inline]
+			pub fn iter_mut(&mut self) -> #iter_mut_ident<'_ #(, #generic_args)*> {
+				#iter_mut_ident {
+					ptrs: self.column_ptrs(),
+					start: 0,
+					end: self.len,
+					_marker: ::core::marker::PhantomData,
+				}
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::Iterator for #into_iter_ident<#(#generic_args,)* #a> #where_clause {
+			type Item = #struct_ty;
+			#[inline]
+			fn next(&mut self) -> Option<#struct_ty> {
+				if self.start == self.end {
+					None
+				} else {
+					let index = self.start;
+					self.start += 1;
+					Some(unsafe { self.read_row(index) })
+				}
+			}
+			#[inline]
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let len = self.end - self.start;
+				(len, Some(len))
+			}
+		}
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::DoubleEndedIterator for #into_iter_ident<#(#generic_args,)* #a> #where_clause {
+			#[inline]
+			fn next_back(&mut self) -> Option<#struct_ty> {
+				if self.start == self.end {
+					None
+				} else {
+					self.end -= 1;
+					Some(unsafe { self.read_row(self.end) })
+				}
+			}
+		}
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::ExactSizeIterator for #into_iter_ident<#(#generic_args,)* #a> #where_clause {}
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::FusedIterator for #into_iter_ident<#(#generic_args,)* #a> #where_clause {}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #into_iter_ident<#(#generic_args,)* #a> #where_clause {
+			/// # Safety
+			///
+			/// `index` must be in `start..end` before it is removed from that range.
+			#[inline]
+			unsafe fn read_row(&self, index: usize) -> #struct_ty {
+				unsafe {
+					#ident {
+						#(
+							#field_members: ::core::ptr::read(
+								self.buf.field_ptr(#field_indices)
+									.cast::<#field_types>()
+									.add(index)
+							),
+						)*
+					}
+				}
+			}
+			#[inline]
+			pub fn allocator(&self) -> &#a {
+				self.buf.allocator()
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> Drop for #into_iter_ident<#(#generic_args,)* #a> #where_clause {
+			fn drop(&mut self) {
+				// The remaining rows are dropped column by column, and RawColVec
+				// frees the buffer afterwards even if a column panics.
+				unsafe {
+					let _columns = (
+						#(
+							::colvec::column::DropColumn::new(
+								self.buf.field_ptr(#field_indices).cast::<#field_types>().add(self.start),
+								self.end - self.start,
+							),
+						)*
+					);
+				}
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::IntoIterator for #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			type Item = #struct_ty;
+			type IntoIter = #into_iter_ident<#(#generic_args,)* #a>;
+			#[inline]
+			fn into_iter(self) -> #into_iter_ident<#(#generic_args,)* #a> {
+				let me = ::core::mem::ManuallyDrop::new(self);
+				#into_iter_ident {
+					// SAFETY: the ColVec is not dropped, so the buffer is moved into the iterator
+					buf: unsafe { ::core::ptr::read(&me.buf) },
+					start: 0,
+					end: me.len,
+				}
+			}
+		}
+		impl<#lt_a, #(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::IntoIterator for &#lt_a #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			type Item = #ref_ident<#lt_a #(, #generic_args)*>;
+			type IntoIter = #iter_ident<#lt_a #(, #generic_args)*>;
+			#[inline]
+			fn into_iter(self) -> #iter_ident<#lt_a #(, #generic_args)*> {
+				self.iter()
+			}
+		}
+		impl<#lt_a, #(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::IntoIterator for &#lt_a mut #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			type Item = #mut_ident<#lt_a #(, #generic_args)*>;
+			type IntoIter = #iter_mut_ident<#lt_a #(, #generic_args)*>;
+			#[inline]
+			fn into_iter(self) -> #iter_mut_ident<#lt_a #(, #generic_args)*> {
+				self.iter_mut()
+			}
+		}
+	};
+
+	let col_slice_ident=syn::Ident::new(&format!("{ident}ColSlice"),ident.span());
+	let col_slice_ref_ident=syn::Ident::new(&format!("{ident}ColSliceRef"),ident.span());
+	let col_slice_mut_ident=syn::Ident::new(&format!("{ident}ColSliceMut"),ident.span());
+	let col_slice = quote! {
+		/// A range of rows with a slice of each column, which is only used behind a reference.
+		///
+		/// This is the column equivalent of `[T]`: the ColVec derefs to one, so functions
+		/// which take a `&ExampleColSlice` or `&mut ExampleColSlice` accept a ColVec as well as
+		/// the sub-ranges returned by `slice` and `split_at`. The columns are dense, so the
+		/// stride between the rows of a column is the size of its field.
+		#[repr(C)]
+		#vis struct #col_slice_ident<#(#generic_params,)*> #where_clause {
+			ptrs: #iter_ptrs,
+			// the slice metadata is the number of rows
+			rows: [()],
+		}
+
+		/// A borrowed sub-range of rows, which derefs to a ColSlice.
+		#vis struct #col_slice_ref_ident<#lt_a, #(#generic_params,)*> #where_clause {
+			ptrs: #iter_ptrs,
+			len: usize,
+			_marker: ::core::marker::PhantomData<&#lt_a #field_types_tuple>,
+		}
+
+		/// A mutably borrowed sub-range of rows, which derefs to a ColSlice.
+		#vis struct #col_slice_mut_ident<#lt_a, #(#generic_params,)*> #where_clause {
+			ptrs: #iter_ptrs,
+			len: usize,
+			_marker: ::core::marker::PhantomData<&#lt_a mut #field_types_tuple>,
+		}
+
+		unsafe impl #impl_generics Send for #col_slice_ident<#(#generic_args),*> where #(#where_preds,)* #(#send_bounds,)* {}
+		unsafe impl #impl_generics Sync for #col_slice_ident<#(#generic_args),*> where #(#where_preds,)* #(#sync_bounds,)* {}
+		unsafe impl #impl_generics Send for #col_slice_ref_ident<'_ #(, #generic_args)*> where #(#where_preds,)* #(#sync_bounds,)* {}
+		unsafe impl #impl_generics Sync for #col_slice_ref_ident<'_ #(, #generic_args)*> where #(#where_preds,)* #(#sync_bounds,)* {}
+		unsafe impl #impl_generics Send for #col_slice_mut_ident<'_ #(, #generic_args)*> where #(#where_preds,)* #(#send_bounds,)* {}
+		unsafe impl #impl_generics Sync for #col_slice_mut_ident<'_ #(, #generic_args)*> where #(#where_preds,)* #(#sync_bounds,)* {}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			#[inline]
+			pub fn as_col_slice(&self) -> &#col_slice_ident<#(#generic_args),*> {
+				self
+			}
+			#[inline]
+			pub fn as_col_slice_mut(&mut self) -> &mut #col_slice_ident<#(#generic_args),*> {
+				self
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::ops::Deref for #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			type Target = #col_slice_ident<#(#generic_args),*>;
+			#[inline]
+			fn deref(&self) -> &#col_slice_ident<#(#generic_args),*> {
+				unsafe { #col_slice_ident::from_header(&self.ptrs, self.len) }
+			}
+		}
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::ops::DerefMut for #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			#[inline]
+			fn deref_mut(&mut self) -> &mut #col_slice_ident<#(#generic_args),*> {
+				unsafe { #col_slice_ident::from_header_mut(&mut self.ptrs, self.len) }
+			}
+		}
+
+		impl #impl_generics #col_slice_ident<#(#generic_args),*> #where_clause {
+			/// # Safety
+			///
+			/// The first `len` rows of the columns in `ptrs` must be initialized
+			/// and shared borrowable for as long as `ptrs` is borrowed.
+			#[inline]
+			const unsafe fn from_header<#lt_s>(ptrs: &#lt_s #iter_ptrs, len: usize) -> &#lt_s Self {
+				let rows = ::core::ptr::slice_from_raw_parts((ptrs as *const #iter_ptrs).cast::<()>(), len);
+				unsafe { &*(rows as *const Self) }
+			}
+			/// # Safety
+			///
+			/// The first `len` rows of the columns in `ptrs` must be initialized
+			/// and mutably borrowable for as long as `ptrs` is borrowed.
+			#[inline]
+			const unsafe fn from_header_mut<#lt_s>(ptrs: &#lt_s mut #iter_ptrs, len: usize) -> &#lt_s mut Self {
+				let rows = ::core::ptr::slice_from_raw_parts_mut((ptrs as *mut #iter_ptrs).cast::<()>(), len);
+				unsafe { &mut *(rows as *mut Self) }
+			}
+			#[inline]
+			pub const fn len(&self) -> usize {
+				self.rows.len()
+			}
+			#[inline]
+			pub const fn is_empty(&self) -> bool {
+				self.len() == 0
+			}
+			#(
+				#[inline]
+				#[allow(dead_code)]
+				#field_vis const fn #field_slice_fn_idents(&self) -> &[#field_types] {
+					unsafe { ::core::slice::from_raw_parts(self.ptrs.#tuple_indices.as_ptr(), self.len()) }
+				}
+				#[inline]
+				#[allow(dead_code)]
+				#field_vis const fn #field_slice_mut_fn_idents(&mut self) -> &mut [#field_types] {
+					unsafe { ::core::slice::from_raw_parts_mut(self.ptrs.#tuple_indices.as_ptr(), self.len()) }
+				}
+			)*
+			#[inline]
+			pub fn get(&self, index: usize) -> Option<#ref_ident<'_ #(, #generic_args)*>> {
+				if index < self.len() {
+					Some(unsafe { let ptrs = self.ptrs; #ptrs_ref })
+				} else {
+					None
+				}
+			}
+			#[inline]
+			pub fn get_mut(&mut self, index: usize) -> Option<#mut_ident<'_ #(, #generic_args)*>> {
+				if index < self.len() {
+					Some(unsafe { let ptrs = self.ptrs; #ptrs_mut })
+				} else {
+					None
+				}
+			}
+			#[inline]
+			pub fn iter(&self) -> #iter_ident<'_ #(, #generic_args)*> {
+				#iter_ident {
+					ptrs: self.ptrs,
+					start: 0,
+					end: self.len(),
+					_marker: ::core::marker::PhantomData,
+				}
+			}
+			#[inline]
+			pub fn iter_mut(&mut self) -> #iter_mut_ident<'_ #(, #generic_args)*> {
+				#iter_mut_ident {
+					ptrs: self.ptrs,
+					start: 0,
+					end: self.len(),
+					_marker: ::core::marker::PhantomData,
+				}
+			}
+			/// Divides one slice into two at an index.
+			///
+			/// # Panics
+			///
+			/// Panics if `mid > len`.
+			#[inline]
+			#[track_caller]
+			pub fn split_at(&self, mid: usize) -> (#col_slice_ref_ident<'_ #(, #generic_args)*>, #col_slice_ref_ident<'_ #(, #generic_args)*>) {
+				assert!(mid <= self.len(), "mid > len");
+				unsafe {(
+					#col_slice_ref_ident::from_ptrs(self.ptrs, 0, mid),
+					#col_slice_ref_ident::from_ptrs(self.ptrs, mid, self.len()),
+				)}
+			}
+			/// Divides one mutable slice into two at an index.
+			///
+			/// # Panics
+			///
+			/// Panics if `mid > len`.
+			#[inline]
+			#[track_caller]
+			pub fn split_at_mut(&mut self, mid: usize) -> (#col_slice_mut_ident<'_ #(, #generic_args)*>, #col_slice_mut_ident<'_ #(, #generic_args)*>) {
+				assert!(mid <= self.len(), "mid > len");
+				unsafe {(
+					#col_slice_mut_ident::from_ptrs(self.ptrs, 0, mid),
+					#col_slice_mut_ident::from_ptrs(self.ptrs, mid, self.len()),
+				)}
+			}
+			/// Borrows a sub-range of the rows.
+			///
+			/// # Panics
+			///
+			/// Panics if the range is out of bounds.
+			#[inline]
+			#[track_caller]
+			pub fn slice<#r>(&self, range: #r) -> #col_slice_ref_ident<'_ #(, #generic_args)*>
+			where
+				#r: ::core::ops::RangeBounds<usize>,
+			{
+				let ::core::ops::Range { start, end } = ::colvec::range::range(range, self.len());
+				unsafe { #col_slice_ref_ident::from_ptrs(self.ptrs, start, end) }
+			}
+			/// Mutably borrows a sub-range of the rows.
+			///
+			/// # Panics
+			///
+			/// Panics if the range is out of bounds.
+			#[inline]
+			#[track_caller]
+			pub fn slice_mut<#r>(&mut self, range: #r) -> #col_slice_mut_ident<'_ #(, #generic_args)*>
+			where
+				#r: ::core::ops::RangeBounds<usize>,
+			{
+				let ::core::ops::Range { start, end } = ::colvec::range::range(range, self.len());
+				unsafe { #col_slice_mut_ident::from_ptrs(self.ptrs, start, end) }
+			}
+		}
+
+		impl<#lt_a, #(#generic_params,)*> #col_slice_ref_ident<#lt_a #(, #generic_args)*> #where_clause {
+			/// # Safety
+			///
+			/// The rows `start..end` of the columns in `ptrs` must be initialized
+			/// and shared borrowable for `'a`.
+			#[inline]
+			unsafe fn from_ptrs(ptrs: #iter_ptrs, start: usize, end: usize) -> Self {
+				debug_assert!(start <= end);
+				Self {
+					ptrs: (#(unsafe { ptrs.#tuple_indices.add(start) },)*),
+					len: end - start,
+					_marker: ::core::marker::PhantomData,
+				}
+			}
+		}
+
+		impl<#lt_a, #(#generic_params,)*> #col_slice_mut_ident<#lt_a #(, #generic_args)*> #where_clause {
+			/// # Safety
+			///
+			/// The rows `start..end` of the columns in `ptrs` must be initialized
+			/// and mutably borrowable for `'a`.
+			#[inline]
+			unsafe fn from_ptrs(ptrs: #iter_ptrs, start: usize, end: usize) -> Self {
+				debug_assert!(start <= end);
+				Self {
+					ptrs: (#(unsafe { ptrs.#tuple_indices.add(start) },)*),
+					len: end - start,
+					_marker: ::core::marker::PhantomData,
+				}
+			}
+		}
+
+		impl #impl_generics ::core::clone::Clone for #col_slice_ref_ident<'_ #(, #generic_args)*> #where_clause {
+			#[inline]
+			fn clone(&self) -> Self {
+				*self
+			}
+		}
+		impl #impl_generics ::core::marker::Copy for #col_slice_ref_ident<'_ #(, #generic_args)*> #where_clause {}
+
+		impl #impl_generics ::core::ops::Deref for #col_slice_ref_ident<'_ #(, #generic_args)*> #where_clause {
+			type Target = #col_slice_ident<#(#generic_args),*>;
+			#[inline]
+			fn deref(&self) -> &#col_slice_ident<#(#generic_args),*> {
+				unsafe { #col_slice_ident::from_header(&self.ptrs, self.len) }
+			}
+		}
+		impl #impl_generics ::core::ops::Deref for #col_slice_mut_ident<'_ #(, #generic_args)*> #where_clause {
+			type Target = #col_slice_ident<#(#generic_args),*>;
+			#[inline]
+			fn deref(&self) -> &#col_slice_ident<#(#generic_args),*> {
+				unsafe { #col_slice_ident::from_header(&self.ptrs, self.len) }
+			}
+		}
+		impl #impl_generics ::core::ops::DerefMut for #col_slice_mut_ident<'_ #(, #generic_args)*> #where_clause {
+			#[inline]
+			fn deref_mut(&mut self) -> &mut #col_slice_ident<#(#generic_args),*> {
+				unsafe { #col_slice_ident::from_header_mut(&mut self.ptrs, self.len) }
+			}
+		}
+
+		impl<#lt_a, #(#generic_params,)*> ::core::iter::IntoIterator for &#lt_a #col_slice_ident<#(#generic_args),*> #where_clause {
+			type Item = #ref_ident<#lt_a #(, #generic_args)*>;
+			type IntoIter = #iter_ident<#lt_a #(, #generic_args)*>;
+			#[inline]
+			fn into_iter(self) -> #iter_ident<#lt_a #(, #generic_args)*> {
+				self.iter()
+			}
+		}
+		impl<#lt_a, #(#generic_params,)*> ::core::iter::IntoIterator for &#lt_a mut #col_slice_ident<#(#generic_args),*> #where_clause {
+			type Item = #mut_ident<#lt_a #(, #generic_args)*>;
+			type IntoIter = #iter_mut_ident<#lt_a #(, #generic_args)*>;
+			#[inline]
+			fn into_iter(self) -> #iter_mut_ident<#lt_a #(, #generic_args)*> {
+				self.iter_mut()
+			}
+		}
+		impl<#lt_s, #lt_a, #(#generic_params,)*> ::core::iter::IntoIterator for &#lt_s #col_slice_ref_ident<#lt_a #(, #generic_args)*> #where_clause {
+			type Item = #ref_ident<#lt_s #(, #generic_args)*>;
+			type IntoIter = #iter_ident<#lt_s #(, #generic_args)*>;
+			#[inline]
+			fn into_iter(self) -> #iter_ident<#lt_s #(, #generic_args)*> {
+				self.iter()
+			}
+		}
+		impl<#lt_s, #lt_a, #(#generic_params,)*> ::core::iter::IntoIterator for &#lt_s mut #col_slice_mut_ident<#lt_a #(, #generic_args)*> #where_clause {
+			type Item = #mut_ident<#lt_s #(, #generic_args)*>;
+			type IntoIter = #iter_mut_ident<#lt_s #(, #generic_args)*>;
+			#[inline]
+			fn into_iter(self) -> #iter_mut_ident<#lt_s #(, #generic_args)*> {
+				self.iter_mut()
+			}
+		}
+	};
+
+	let columns_ident=syn::Ident::new(&format!("{ident}Columns"),ident.span());
+	let columns_mut_ident=syn::Ident::new(&format!("{ident}ColumnsMut"),ident.span());
+	// build the columns out of the column pointers in `ptrs` with `len` rows
+	let ptrs_columns=quote!{#columns_ident{
+		#(#field_members: ::core::slice::from_raw_parts(ptrs.#tuple_indices.as_ptr(), len),)*
+		#marker_init
+	}};
+	let ptrs_columns_mut=quote!{#columns_mut_ident{
+		#(#field_members: ::core::slice::from_raw_parts_mut(ptrs.#tuple_indices.as_ptr(), len),)*
+		#marker_init
+	}};
+	let columns_body=view_body(field_types.iter().map(|ty|syn::parse_quote!{&#lt_a [#ty]}).collect());
+	let columns_mut_body=view_body(field_types.iter().map(|ty|syn::parse_quote!{&#lt_a mut [#ty]}).collect());
+	let columns = quote! {
+		/// A shared slice of every column, borrowed all at once.
+		#vis struct #columns_ident<#lt_a, #(#generic_params,)*> #columns_body
+
+		impl #impl_generics ::core::clone::Clone for #columns_ident<'_ #(, #generic_args)*> #where_clause {
+			#[inline]
+			fn clone(&self) -> Self {
+				*self
+			}
+		}
+		impl #impl_generics ::core::marker::Copy for #columns_ident<'_ #(, #generic_args)*> #where_clause {}
+
+		/// A mutable slice of every column, borrowed all at once so that
+		/// disjoint columns can be used at the same time.
+		#vis struct #columns_mut_ident<#lt_a, #(#generic_params,)*> #columns_mut_body
+	};
+
+	let colvec_columns = quote! {
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			#[inline]
+			pub fn columns(&self) -> #columns_ident<'_ #(, #generic_args)*> {
+				let ptrs = self.column_ptrs();
+				let len = self.len;
+				unsafe { #ptrs_columns }
+			}
+			#[inline]
+			pub fn columns_mut(&mut self) -> #columns_mut_ident<'_ #(, #generic_args)*> {
+				let ptrs = self.column_ptrs();
+				let len = self.len;
+				unsafe { #ptrs_columns_mut }
+			}
+		}
+
+		impl #impl_generics #col_slice_ident<#(#generic_args),*> #where_clause {
+			#[inline]
+			pub fn columns(&self) -> #columns_ident<'_ #(, #generic_args)*> {
+				let ptrs = self.ptrs;
+				let len = self.len();
+				unsafe { #ptrs_columns }
+			}
+			#[inline]
+			pub fn columns_mut(&mut self) -> #columns_mut_ident<'_ #(, #generic_args)*> {
+				let ptrs = self.ptrs;
+				let len = self.len();
+				unsafe { #ptrs_columns_mut }
+			}
+		}
+	};
+
+	// names the generated items for code which is generic over the struct
+	let columnar = quote! {
+		impl #impl_generics ::colvec::Columnar for #struct_ty #where_clause {
+			const LAYOUT: ::core::alloc::Layout = <Self as ::colvec::fields::StructInfo<#fields_count>>::LAYOUT;
+			const FIELD_LAYOUTS: &'static [::core::alloc::Layout] = &<Self as ::colvec::fields::StructInfo<#fields_count>>::FIELDS.field_layouts();
+
+			type ColVec<#a: ::colvec::alloc::Allocator> = #colvec_ident<#(#generic_args,)* #a>;
+			type Ref<#lt_a> = #ref_ident<#lt_a #(, #generic_args)*> where Self: #lt_a;
+			type Mut<#lt_a> = #mut_ident<#lt_a #(, #generic_args)*> where Self: #lt_a;
+			type Columns<#lt_a> = #columns_ident<#lt_a #(, #generic_args)*> where Self: #lt_a;
+
+			#[inline]
+			fn new_in<#a: ::colvec::alloc::Allocator>(alloc: #a) -> Self::ColVec<#a> {
+				#colvec_ident::new_in(alloc)
+			}
+			#[inline]
+			#[track_caller]
+			fn with_capacity_in<#a: ::colvec::alloc::Allocator>(capacity: usize, alloc: #a) -> Self::ColVec<#a> {
+				#colvec_ident::with_capacity_in(capacity, alloc)
+			}
+			#[inline]
+			fn len<#a: ::colvec::alloc::Allocator>(colvec: &Self::ColVec<#a>) -> usize {
+				colvec.len()
+			}
+			#[inline]
+			fn capacity<#a: ::colvec::alloc::Allocator>(colvec: &Self::ColVec<#a>) -> usize {
+				colvec.capacity()
+			}
+			#[inline]
+			fn allocator<#a: ::colvec::alloc::Allocator>(colvec: &Self::ColVec<#a>) -> &#a {
+				colvec.allocator()
+			}
+			#[inline]
+			#[track_caller]
+			fn reserve<#a: ::colvec::alloc::Allocator>(colvec: &mut Self::ColVec<#a>, additional: usize) {
+				colvec.reserve(additional)
+			}
+			#[inline]
+			fn push<#a: ::colvec::alloc::Allocator>(colvec: &mut Self::ColVec<#a>, value: Self) {
+				colvec.push(value)
+			}
+			#[inline]
+			fn pop<#a: ::colvec::alloc::Allocator>(colvec: &mut Self::ColVec<#a>) -> Option<Self> {
+				colvec.pop()
+			}
+			#[inline]
+			fn truncate<#a: ::colvec::alloc::Allocator>(colvec: &mut Self::ColVec<#a>, len: usize) {
+				colvec.truncate(len)
+			}
+			#[inline]
+			fn get<#lt_a, #a: ::colvec::alloc::Allocator>(colvec: &#lt_a Self::ColVec<#a>, index: usize) -> Option<Self::Ref<#lt_a>> where Self: #lt_a {
+				colvec.get(index)
+			}
+			#[inline]
+			fn get_mut<#lt_a, #a: ::colvec::alloc::Allocator>(colvec: &#lt_a mut Self::ColVec<#a>, index: usize) -> Option<Self::Mut<#lt_a>> where Self: #lt_a {
+				colvec.get_mut(index)
+			}
+			#[inline]
+			fn columns<#lt_a, #a: ::colvec::alloc::Allocator>(colvec: &#lt_a Self::ColVec<#a>) -> Self::Columns<#lt_a> where Self: #lt_a {
+				colvec.columns()
+			}
+		}
+
+		impl #impl_generics ::colvec::ColumnarMut for #struct_ty #where_clause {
+			type ColumnsMut<#lt_a> = #columns_mut_ident<#lt_a #(, #generic_args)*> where Self: #lt_a;
+
+			#[inline]
+			fn columns_mut<#lt_a, #a: ::colvec::alloc::Allocator>(colvec: &#lt_a mut Self::ColVec<#a>) -> Self::ColumnsMut<#lt_a> where Self: #lt_a {
+				colvec.columns_mut()
+			}
+		}
+	};
+
+	let sort = quote! {
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator + ::core::clone::Clone> #colvec_ident<#(#generic_args,)* #a>
+		where #(#where_preds,)*
+			#(#clone_bounds,)*
+		{
+			/// Clones the rows at `indices`, in order, into a new ColVec.
+			///
+			/// # Panics
+			///
+			/// Panics if an index is out of bounds.
+			#[track_caller]
+			pub fn gather(&self, indices: &[usize]) -> Self {
+				#[cold]
+				#[track_caller]
+				fn assert_failed(index: usize, len: usize) -> ! {
+					panic!("gather index (is {index}) should be < len (is {len})");
+				}
+
+				let len = self.len;
+				if let Some(&index) = indices.iter().find(|&&index| index >= len) {
+					assert_failed(index, len);
+				}
+				let mut colvec = Self::with_capacity_in(indices.len(), self.allocator().clone());
+				// same as Clone, each column is owned by a guard until every column is gathered
+				let _columns = ::core::mem::ManuallyDrop::new((
+					#(
+						unsafe {
+							::colvec::column::gather_column(
+								self.buf.field_ptr(#field_indices).cast::<#field_types>(),
+								colvec.buf.field_ptr(#field_indices).cast::<#field_types>(),
+								indices,
+							)
+						},
+					)*
+				));
+				colvec.len = indices.len();
+				colvec
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			/// The index of every row, to be sorted and then applied as a permutation.
+			#[inline]
+			fn identity_permutation(&self) -> ::colvec::vec::Vec<usize> {
+				(0..self.len).collect()
+			}
+			/// Moves the row at `permutation[i]` to `i` in every column.
+			///
+			/// # Safety
+			///
+			/// `permutation` must contain every index in `0..self.len()` exactly once.
+			#[inline]
+			unsafe fn apply_permutation_unchecked(&mut self, permutation: &mut [usize]) {
+				debug_assert_eq!(permutation.len(), self.len);
+				unsafe {
+					self.buf.apply_permutation(
+						permutation,
+					);
+				}
+			}
+			/// Moves the row at `permutation[i]` to `i`, one column at a time.
+			///
+			/// The permutation is borrowed mutably to mark visited rows,
+			/// and holds the same indices again once this returns.
+			///
+			/// # Panics
+			///
+			/// Panics if `permutation` does not contain every index in `0..self.len()` exactly once.
+			#[track_caller]
+			pub fn apply_permutation(&mut self, permutation: &mut [usize]) {
+				assert_eq!(permutation.len(), self.len, "permutation length does not match the number of rows");
+				assert!(::colvec::fields::is_permutation(permutation), "invalid permutation");
+				unsafe { self.apply_permutation_unchecked(permutation) }
+			}
+			/// Moves each row of `source` into the row at the matching index, dropping the row it replaces.
+			///
+			/// # Panics
+			///
+			/// Panics if the lengths of `indices` and `source` differ, or if an index is out of bounds.
+			#[track_caller]
+			pub fn scatter<#a2: ::colvec::alloc::Allocator>(&mut self, indices: &[usize], source: #colvec_ident<#(#generic_args,)* #a2>) {
+				assert_eq!(indices.len(), source.len(), "indices length does not match the number of rows");
+				#[cold]
+				#[track_caller]
+				fn assert_failed(index: usize, len: usize) -> ! {
+					panic!("scatter index (is {index}) should be < len (is {len})");
+				}
+
+				let len = self.len;
+				if let Some(&index) = indices.iter().find(|&&index| index >= len) {
+					assert_failed(index, len);
+				}
+				for (&index, row) in indices.iter().zip(source) {
+					// the replaced row is dropped once the new row is in place
+					let _old = unsafe { self.read_row(index) };
+					unsafe { self.write_row(index, row) };
+				}
+			}
+			/// Sorts the rows with a comparison function, preserving the initial order of equal rows.
+			///
+			/// The permutation is computed before any row moves, so a panicking
+			/// comparison leaves the ColVec unchanged.
+			pub fn sort_by<#f>(&mut self, mut compare: #f)
+			where
+				#f: FnMut(#ref_ident<'_ #(, #generic_args)*>, #ref_ident<'_ #(, #generic_args)*>) -> ::core::cmp::Ordering,
+			{
+				let mut permutation = self.identity_permutation();
+				permutation.sort_by(|&a, &b| unsafe { compare(self.get_unchecked(a), self.get_unchecked(b)) });
+				unsafe { self.apply_permutation_unchecked(&mut permutation) }
+			}
+			/// Sorts the rows with a key extraction function, preserving the initial order of equal rows.
+			pub fn sort_by_key<#k, #f>(&mut self, mut f: #f)
+			where
+				#f: FnMut(#ref_ident<'_ #(, #generic_args)*>) -> #k,
+				#k: ::core::cmp::Ord,
+			{
+				let mut permutation = self.identity_permutation();
+				permutation.sort_by_key(|&index| unsafe { f(self.get_unchecked(index)) });
+				unsafe { self.apply_permutation_unchecked(&mut permutation) }
+			}
+			/// Sorts the rows with a key extraction function, calling it only once per row.
+			pub fn sort_by_cached_key<#k, #f>(&mut self, mut f: #f)
+			where
+				#f: FnMut(#ref_ident<'_ #(, #generic_args)*>) -> #k,
+				#k: ::core::cmp::Ord,
+			{
+				let mut permutation = self.identity_permutation();
+				permutation.sort_by_cached_key(|&index| unsafe { f(self.get_unchecked(index)) });
+				unsafe { self.apply_permutation_unchecked(&mut permutation) }
+			}
+			/// Sorts the rows with a comparison function, without preserving the initial order of equal rows.
+			pub fn sort_unstable_by<#f>(&mut self, mut compare: #f)
+			where
+				#f: FnMut(#ref_ident<'_ #(, #generic_args)*>, #ref_ident<'_ #(, #generic_args)*>) -> ::core::cmp::Ordering,
+			{
+				let mut permutation = self.identity_permutation();
+				permutation.sort_unstable_by(|&a, &b| unsafe { compare(self.get_unchecked(a), self.get_unchecked(b)) });
+				unsafe { self.apply_permutation_unchecked(&mut permutation) }
+			}
+			/// Sorts the rows with a key extraction function, without preserving the initial order of equal rows.
+			pub fn sort_unstable_by_key<#k, #f>(&mut self, mut f: #f)
+			where
+				#f: FnMut(#ref_ident<'_ #(, #generic_args)*>) -> #k,
+				#k: ::core::cmp::Ord,
+			{
+				let mut permutation = self.identity_permutation();
+				permutation.sort_unstable_by_key(|&index| unsafe { f(self.get_unchecked(index)) });
+				unsafe { self.apply_permutation_unchecked(&mut permutation) }
+			}
+			/// Checks if the rows are sorted by the key extracted from each of them.
+			pub fn is_sorted_by_key<#k, #f>(&self, f: #f) -> bool
+			where
+				#f: FnMut(#ref_ident<'_ #(, #generic_args)*>) -> #k,
+				#k: ::core::cmp::PartialOrd,
+			{
+				self.iter().map(f).is_sorted()
+			}
+			/// Binary searches the sorted rows with a comparison function.
+			///
+			/// Returns the index of a matching row, or the index where a matching
+			/// row could be inserted while keeping the rows sorted.
+			pub fn binary_search_by<#lt_a, #f>(&#lt_a self, mut f: #f) -> Result<usize, usize>
+			where
+				#f: FnMut(#ref_ident<#lt_a #(, #generic_args)*>) -> ::core::cmp::Ordering,
+			{
+				let mut size = self.len;
+				if size == 0 {
+					return Err(0);
+				}
+				let mut base = 0;
+				while size > 1 {
+					let half = size / 2;
+					let mid = base + half;
+					let cmp = f(unsafe { self.get_unchecked(mid) });
+					base = if cmp == ::core::cmp::Ordering::Greater { base } else { mid };
+					size -= half;
+				}
+				match f(unsafe { self.get_unchecked(base) }) {
+					::core::cmp::Ordering::Equal => Ok(base),
+					cmp => Err(base + (cmp == ::core::cmp::Ordering::Less) as usize),
+				}
+			}
+			/// Binary searches the rows, which are sorted by the key extracted from each of them.
+			pub fn binary_search_by_key<#lt_a, #b, #f>(&#lt_a self, b: &#b, mut f: #f) -> Result<usize, usize>
+			where
+				#f: FnMut(#ref_ident<#lt_a #(, #generic_args)*>) -> #b,
+				#b: ::core::cmp::Ord,
+			{
+				self.binary_search_by(|row| f(row).cmp(b))
+			}
+		}
+	};
+
+	let extract_if_ident=syn::Ident::new(&format!("{ident}ExtractIf"),ident.span());
+	let drain = quote! {
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			/// Removes the rows in `range`, returning them as an iterator.
+			///
+			/// The remaining rows are shifted down once in every column when the
+			/// iterator is dropped.
+			#[track_caller]
+			pub fn drain<#r>(&mut self, range: #r) -> #drain_ident<'_ #(, #generic_args)*, #a>
+			where
+				#r: ::core::ops::RangeBounds<usize>,
+			{
+				let len = self.len;
+				let ::core::ops::Range { start, end } = ::colvec::range::range(range, len);
+				// Truncate the vec to the start of the range first, so that the removed
+				// rows and the tail are not touched again if the Drain is leaked.
+				self.len = start;
+				#drain_ident {
+					vec: self,
+					idx: start,
+					end,
+					tail_start: end,
+					tail_len: len - end,
+				}
+			}
+			/// Retains only the rows for which `f` returns `true`, compacting every
+			/// column in a single pass.
+			pub fn retain<#f>(&mut self, mut f: #f)
+			where
+				#f: FnMut(#ref_ident<'_ #(, #generic_args)*>) -> bool,
+			{
+				let ptrs = self.column_ptrs();
+				unsafe {
+					self.buf.retain(
+						&mut self.len,
+						|index| f(#ptrs_ref),
+						|index| { let _columns = (#(::colvec::column::DropColumn::new(ptrs.#tuple_indices.as_ptr().add(index), 1),)*); },
+					);
+				}
+			}
+			/// Retains only the rows for which `f` returns `true`, passing a mutable
+			/// view of each row to `f`.
+			pub fn retain_mut<#f>(&mut self, mut f: #f)
+			where
+				#f: FnMut(#mut_ident<'_ #(, #generic_args)*>) -> bool,
+			{
+				let ptrs = self.column_ptrs();
+				unsafe {
+					self.buf.retain(
+						&mut self.len,
+						|index| f(#ptrs_mut),
+						|index| { let _columns = (#(::colvec::column::DropColumn::new(ptrs.#tuple_indices.as_ptr().add(index), 1),)*); },
+					);
+				}
+			}
+			/// Creates an iterator which removes and yields the rows in `range`
+			/// for which `filter` returns `true`.
+			///
+			/// Rows which are kept are shifted down as the iterator advances, and the
+			/// rest of the ColVec is shifted down once when the iterator is dropped.
+			#[track_caller]
+			pub fn extract_if<#f, #r>(&mut self, range: #r, filter: #f) -> #extract_if_ident<'_ #(, #generic_args)*, #f, #a>
+			where
+				#f: FnMut(#mut_ident<'_ #(, #generic_args)*>) -> bool,
+				#r: ::core::ops::RangeBounds<usize>,
+			{
+				let old_len = self.len;
+				let ::core::ops::Range { start, end } = ::colvec::range::range(range, old_len);
+
+				// Guard against the vec getting leaked (leak amplification)
+				self.len = 0;
+				#extract_if_ident { vec: self, idx: start, del: 0, end, old_len, pred: filter }
+			}
+		}
+
+		/// A draining iterator over the rows of a ColVec.
+		#vis struct #drain_ident<#lt_a, #(#generic_params,)* #a: ::colvec::alloc::Allocator> #where_clause {
+			vec: &#lt_a mut #colvec_ident<#(#generic_args,)* #a>,
+			idx: usize,
+			end: usize,
+			tail_start: usize,
+			tail_len: usize,
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::Iterator for #drain_ident<'_ #(, #generic_args)*, #a> #where_clause {
+			type Item = #struct_ty;
+			#[inline]
+			fn next(&mut self) -> Option<#struct_ty> {
+				if self.idx == self.end {
+					None
+				} else {
+					let index = self.idx;
+					self.idx += 1;
+					Some(unsafe { self.vec.read_row(index) })
+				}
+			}
+			#[inline]
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let len = self.end - self.idx;
+				(len, Some(len))
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::DoubleEndedIterator for #drain_ident<'_ #(, #generic_args)*, #a> #where_clause {
+			#[inline]
+			fn next_back(&mut self) -> Option<#struct_ty> {
+				if self.idx == self.end {
+					None
+				} else {
+					self.end -= 1;
+					Some(unsafe { self.vec.read_row(self.end) })
+				}
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::ExactSizeIterator for #drain_ident<'_ #(, #generic_args)*, #a> #where_clause {}
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::FusedIterator for #drain_ident<'_ #(, #generic_args)*, #a> #where_clause {}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> Drop for #drain_ident<'_ #(, #generic_args)*, #a> #where_clause {
+			fn drop(&mut self) {
+				/// Moves back the un-`Drain`ed rows to restore the original ColVec.
+				struct DropGuard<#lt_r, #lt_a, #(#generic_params,)* #a: ::colvec::alloc::Allocator>(&#lt_r mut #drain_ident<#lt_a #(, #generic_args)*, #a>) #where_clause;
+
+				impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> Drop for DropGuard<'_, '_, #(#generic_args,)* #a> #where_clause {
+					fn drop(&mut self) {
+						if self.0.tail_len > 0 {
+							let vec = &mut *self.0.vec;
+							// memmove back untouched tail, update to new length
+							let start = vec.len;
+							let tail = self.0.tail_start;
+							if tail != start {
+								unsafe {
+									vec.buf.copy_rows(
+										tail,
+										start,
+										self.0.tail_len,
+									);
+								}
+							}
+							vec.len = start + self.0.tail_len;
+						}
+					}
+				}
+
+				let (idx, end) = (self.idx, self.end);
+				self.idx = end;
+
+				// ensure the tail is moved back even if dropping a row panics
+				let guard = DropGuard(self);
+				unsafe { guard.0.vec.drop_rows(idx, end - idx) };
+			}
+		}
+
+		/// An iterator which uses a closure to determine if a row should be removed.
+		#vis struct #extract_if_ident<#lt_a, #(#generic_params,)* #f, #a: ::colvec::alloc::Allocator> #where_clause {
+			vec: &#lt_a mut #colvec_ident<#(#generic_args,)* #a>,
+			/// The index of the row that will be inspected by the next call to `next`.
+			idx: usize,
+			/// Elements at and beyond this point will be retained. Must be equal or smaller than `old_len`.
+			end: usize,
+			/// The number of rows that have been removed thus far.
+			del: usize,
+			/// The original length of `vec` prior to draining.
+			old_len: usize,
+			/// The filter test predicate.
+			pred: #f,
+		}
+
+		impl<#(#generic_params,)* #f, #a: ::colvec::alloc::Allocator> ::core::iter::Iterator for #extract_if_ident<'_ #(, #generic_args)*, #f, #a>
+		where #(#where_preds,)*
+			#f: FnMut(#mut_ident<'_ #(, #generic_args)*>) -> bool,
+		{
+			type Item = #struct_ty;
+			fn next(&mut self) -> Option<#struct_ty> {
+				while self.idx < self.end {
+					let index = self.idx;
+					let ptrs = self.vec.column_ptrs();
+					let drained = (self.pred)(unsafe { #ptrs_mut });
+					// Update the index *after* the predicate is called. If the index
+					// is updated prior and the predicate panics, the row at this
+					// index would be leaked.
+					self.idx += 1;
+					if drained {
+						self.del += 1;
+						return Some(unsafe { self.vec.read_row(index) });
+					} else if self.del > 0 {
+						unsafe {
+							self.vec.buf.copy_rows(
+								index,
+								index - self.del,
+								1,
+							);
+						}
+					}
+				}
+				None
+			}
+			#[inline]
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				(0, Some(self.end - self.idx))
+			}
+		}
+
+		impl<#(#generic_params,)* #f, #a: ::colvec::alloc::Allocator> Drop for #extract_if_ident<'_ #(, #generic_args)*, #f, #a> #where_clause {
+			fn drop(&mut self) {
+				if self.del > 0 {
+					// This is safe because `self.vec` has not been modified since
+					// `extract_if` was called, except for the rows which were shifted down.
+					unsafe {
+						self.vec.buf.copy_rows(
+							self.idx,
+							self.idx - self.del,
+							self.old_len - self.idx,
+						);
+					}
+				}
+				self.vec.len = self.old_len - self.del;
+			}
+		}
+	};
+
+	let copy_bounds=field_bounds(&[syn::parse_quote!{#struct_ty}],syn::parse_quote!{::core::marker::Copy});
+	let conversions = quote! {
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			// shared by the Extend, FromIterator and From impls
+			#[track_caller]
+			fn extend_desugared<#i: ::core::iter::Iterator<Item = #struct_ty>>(&mut self, mut iterator: #i) {
+				// Reserve for the whole iterator up front, so that an accurate size_hint
+				// grows the buffer once instead of once per row.
+				let (lower, _) = iterator.size_hint();
+				self.reserve(lower);
+				while let Some(element) = iterator.next() {
+					let len = self.len;
+					if len == self.capacity() {
+						let (lower, _) = iterator.size_hint();
+						self.reserve(lower.saturating_add(1));
+					}
+					unsafe {
+						self.write_row(len, element);
+					}
+					// Since next() executes user code which can panic we have to bump the length
+					// after each step.
+					self.len = len + 1;
+				}
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::Extend<#struct_ty> for #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			#[inline]
+			#[track_caller]
+			fn extend<#i: ::core::iter::IntoIterator<Item = #struct_ty>>(&mut self, iter: #i) {
+				self.extend_desugared(iter.into_iter())
+			}
+		}
+
+		impl<#lt_a, #(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::Extend<&#lt_a #struct_ty> for #colvec_ident<#(#generic_args,)* #a>
+		where #(#where_preds,)*
+			#(#copy_bounds,)*
+		{
+			#[inline]
+			#[track_caller]
+			fn extend<#i: ::core::iter::IntoIterator<Item = &#lt_a #struct_ty>>(&mut self, iter: #i) {
+				self.extend_desugared(iter.into_iter().copied())
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::convert::From<#colvec_ident<#(#generic_args,)* #a>> for ::colvec::vec::Vec<#struct_ty> #where_clause {
+			#[inline]
+			fn from(colvec: #colvec_ident<#(#generic_args,)* #a>) -> Self {
+				let mut vec = ::colvec::vec::Vec::with_capacity(colvec.len());
+				vec.extend(colvec);
+				vec
+			}
+		}
+	};
+
+	#[cfg(feature = "std")]
+	let columns_owned = {
+		let columns_owned_ident=syn::Ident::new(&format!("{ident}ColumnsOwned"),ident.span());
+		let vec_types=field_types.iter().map(|ty|quote!{::colvec::vec::Vec<#ty>}).collect::<Vec<_>>();
+		let columns_owned_body=if is_tuple{
+			quote!{(#(#field_vis #vec_types,)*) #where_clause;}
+		}else{
+			quote!{#where_clause {
+				#(#field_vis #field_members: #vec_types,)*
+			}}
+		};
+		// the Vecs are moved into and out of columns with their own allocations as they are,
+		// and are copied into and out of a contiguous allocation
+		let (adopt_columns,into_columns)=if contiguous{
+			(None,quote!{
+				let mut colvec = self;
+				let len = colvec.len;
+				let ptrs = colvec.column_ptrs();
+				// the values are moved out, so only the allocation is freed when colvec is dropped
+				colvec.len = 0;
+				(#({
+					let mut column = ::colvec::vec::Vec::with_capacity(len);
+					unsafe {
+						::core::ptr::copy_nonoverlapping(ptrs.#tuple_indices.as_ptr(), column.as_mut_ptr(), len);
+						column.set_len(len);
+					}
+					column
+				},)*)
+			})
+		}else{
+			(Some(quote!{
+				// Vecs which share a capacity have the same layout as the columns, so they are adopted
+				let capacities: [Option<usize>; #fields_count] = [#(
+					if ::core::mem::size_of::<#field_types>() == 0 { None } else { Some(columns.#tuple_indices.capacity()) }
+				),*];
+				let mut allocated = capacities.into_iter().flatten();
+				let capacity = allocated.next().unwrap_or(0);
+				if allocated.all(|other| other == capacity) {
+					let mut columns = ::core::mem::ManuallyDrop::new(columns);
+					let ptrs = [#(columns.#tuple_indices.as_mut_ptr().cast::<u8>()),*];
+					let buf = unsafe { #raw::from_raw_parts_in(ptrs, capacity, ::colvec::alloc::Global) };
+					return Self::from_buf(buf, len);
+				}
+			}),quote!{
+				let colvec = ::core::mem::ManuallyDrop::new(self);
+				let len = colvec.len;
+				let capacity = colvec.capacity();
+				let ptrs = colvec.column_ptrs();
+				// each column was allocated like the buffer of a Vec with the same capacity
+				unsafe { (#(::colvec::vec::Vec::from_raw_parts(ptrs.#tuple_indices.as_ptr(), len, capacity),)*) }
+			})
+		};
+	quote! {
+			/// Every column as a Vec, which is a ColVec taken apart.
+			#vis struct #columns_owned_ident<#(#generic_params,)*> #columns_owned_body
+
+			impl #impl_generics #colvec_ident<#(#generic_args,)* ::colvec::alloc::Global> #where_clause {
+				/// Builds a ColVec out of a Vec for each column.
+				///
+				/// # Panics
+				///
+				/// Panics if the columns do not all have the same length.
+				#[track_caller]
+				#[allow(unused_variables)]
+				pub fn from_columns(#(#column_names: #vec_types),*) -> Self {
+					let columns = (#(#column_names,)*);
+					let lens: [usize; #fields_count] = [#(columns.#tuple_indices.len()),*];
+					let len = lens.first().copied().unwrap_or(0);
+					assert!(lens.iter().all(|&column_len| column_len == len), "every column should have the same length, but the lengths are {lens:?}");
+					#adopt_columns
+					let mut colvec = Self::with_capacity(len);
+					let ptrs = colvec.column_ptrs();
+					#(
+						let mut column = columns.#tuple_indices;
+						unsafe {
+							::core::ptr::copy_nonoverlapping(column.as_ptr(), ptrs.#tuple_indices.as_ptr(), len);
+							// the values belong to colvec now, so only the allocation is freed
+							column.set_len(0);
+						}
+					)*
+					colvec.len = len;
+					colvec
+				}
+				/// Takes the ColVec apart into a Vec for each column.
+				#[allow(clippy::unused_unit)]
+				pub fn into_columns(self) -> (#(#vec_types,)*) {
+					#into_columns
+				}
+			}
+
+			impl #impl_generics ::core::convert::From<#columns_owned_ident<#(#generic_args),*>> for #colvec_ident<#(#generic_args,)* ::colvec::alloc::Global> #where_clause {
+				#[inline]
+				#[track_caller]
+				fn from(columns: #columns_owned_ident<#(#generic_args),*>) -> Self {
+					Self::from_columns(#(columns.#field_members),*)
+				}
+			}
+
+			impl #impl_generics ::core::convert::From<#colvec_ident<#(#generic_args,)* ::colvec::alloc::Global>> for #columns_owned_ident<#(#generic_args),*> #where_clause {
+				#[inline]
+				fn from(colvec: #colvec_ident<#(#generic_args,)* ::colvec::alloc::Global>) -> Self {
+					let columns = colvec.into_columns();
+					Self {
+						#(#field_members: columns.#tuple_indices,)*
+					}
+				}
+			}
+		}
+	};
+
+	#[cfg(feature = "std")]
+	let global_conversions = quote! {
+		impl #impl_generics ::core::iter::FromIterator<#struct_ty> for #colvec_ident<#(#generic_args,)* ::colvec::alloc::Global> #where_clause {
+			#[inline]
+			#[track_caller]
+			fn from_iter<#i: ::core::iter::IntoIterator<Item = #struct_ty>>(iter: #i) -> Self {
+				let mut colvec = Self::new();
+				colvec.extend_desugared(iter.into_iter());
+				colvec
+			}
+		}
+
+		impl #impl_generics ::core::convert::From<::colvec::vec::Vec<#struct_ty>> for #colvec_ident<#(#generic_args,)* ::colvec::alloc::Global> #where_clause {
+			#[inline]
+			#[track_caller]
+			fn from(vec: ::colvec::vec::Vec<#struct_ty>) -> Self {
+				let mut colvec = Self::with_capacity(vec.len());
+				colvec.extend_desugared(vec.into_iter());
+				colvec
+			}
+		}
+
+		impl<#(#generic_params,)* const #k: usize> ::core::convert::From<[#struct_ty; #k]> for #colvec_ident<#(#generic_args,)* ::colvec::alloc::Global> #where_clause {
+			#[inline]
+			#[track_caller]
+			fn from(array: [#struct_ty; #k]) -> Self {
+				let mut colvec = Self::with_capacity(#k);
+				colvec.extend_desugared(array.into_iter());
+				colvec
+			}
+		}
+	};
+
+	let ident_string=ident.to_string();
+	let debug_bounds=field_bounds(&field_types,syn::parse_quote!{::core::fmt::Debug});
+	let partial_eq_bounds=field_bounds(&field_types,syn::parse_quote!{::core::cmp::PartialEq});
+	let eq_bounds=field_bounds(&field_types,syn::parse_quote!{::core::cmp::Eq});
+	let hash_bounds=field_bounds(&field_types,syn::parse_quote!{::core::hash::Hash});
+	let debug_fields=if is_tuple{
+		quote!{
+			f.debug_tuple(#ident_string)
+				#(.field(&self.#field_members))*
+				.finish()
+		}
+	}else{
+		let field_ident_strings=field_members.iter().map(|member|match member{
+			syn::Member::Named(ident)=>ident.to_string(),
+			syn::Member::Unnamed(index)=>index.index.to_string(),
+		});
+		quote!{
+			f.debug_struct(#ident_string)
+				#(.field(#field_ident_strings, &self.#field_members))*
+				.finish()
+		}
+	};
+	let debug_row=quote!{
+		fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+			#debug_fields
+		}
+	};
+	let debug_views = quote! {
+		impl #impl_generics ::core::fmt::Debug for #ref_ident<'_ #(, #generic_args)*>
+		where #(#where_preds,)*
+			#(#debug_bounds,)*
+		{
+			#debug_row
+		}
+
+		impl #impl_generics ::core::fmt::Debug for #mut_ident<'_ #(, #generic_args)*>
+		where #(#where_preds,)*
+			#(#debug_bounds,)*
+		{
+			#debug_row
+		}
+	};
+
+	let traits = quote! {
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator + ::core::clone::Clone> ::core::clone::Clone for #colvec_ident<#(#generic_args,)* #a>
+		where #(#where_preds,)*
+			#(#clone_bounds,)*
+		{
+			#[track_caller]
+			fn clone(&self) -> Self {
+				let len = self.len;
+				let mut colvec = Self::with_capacity_in(len, self.allocator().clone());
+				// Each column is cloned in one pass and owned by a guard until every column
+				// is cloned, so a panicking clone drops the columns which were already cloned.
+				let _columns = ::core::mem::ManuallyDrop::new((
+					#(
+						unsafe {
+							::colvec::column::clone_column(
+								self.buf.field_ptr(#field_indices).cast::<#field_types>(),
+								colvec.buf.field_ptr(#field_indices).cast::<#field_types>(),
+								len,
+							)
+						},
+					)*
+				));
+				// every column is cloned, hand them over to the ColVec
+				colvec.len = len;
+				colvec
+			}
+		}
+
+		impl #impl_generics ::core::fmt::Debug for #col_slice_ident<#(#generic_args),*>
+		where #(#where_preds,)*
+			#(#debug_bounds,)*
+		{
+			fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+				f.debug_list().entries(self.iter()).finish()
+			}
+		}
+
+		impl #impl_generics ::core::fmt::Debug for #col_slice_ref_ident<'_ #(, #generic_args)*>
+		where #(#where_preds,)*
+			#(#debug_bounds,)*
+		{
+			fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+				f.debug_list().entries(self.iter()).finish()
+			}
+		}
+
+		impl #impl_generics ::core::fmt::Debug for #col_slice_mut_ident<'_ #(, #generic_args)*>
+		where #(#where_preds,)*
+			#(#debug_bounds,)*
+		{
+			fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+				f.debug_list().entries(self.iter()).finish()
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::fmt::Debug for #colvec_ident<#(#generic_args,)* #a>
+		where #(#where_preds,)*
+			#(#debug_bounds,)*
+		{
+			fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+				f.debug_list().entries(self.iter()).finish()
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator, #a2: ::colvec::alloc::Allocator> ::core::cmp::PartialEq<#colvec_ident<#(#generic_args,)* #a2>> for #colvec_ident<#(#generic_args,)* #a>
+		where #(#where_preds,)*
+			#(#partial_eq_bounds,)*
+		{
+			#[inline]
+			fn eq(&self, other: &#colvec_ident<#(#generic_args,)* #a2>) -> bool {
+				self.len == other.len #(&& self.#field_slice_fn_idents() == other.#field_slice_fn_idents())*
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::cmp::Eq for #colvec_ident<#(#generic_args,)* #a>
+		where #(#where_preds,)*
+			#(#eq_bounds,)*
+		{}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::hash::Hash for #colvec_ident<#(#generic_args,)* #a>
+		where #(#where_preds,)*
+			#(#hash_bounds,)*
+		{
+			#[inline]
+			fn hash<#h: ::core::hash::Hasher>(&self, state: &mut #h) {
+				state.write_usize(self.len);
+				#(::core::hash::Hash::hash(self.#field_slice_fn_idents(), state);)*
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator + ::core::default::Default> ::core::default::Default for #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			#[inline]
+			fn default() -> Self {
+				Self::new_in(#a::default())
+			}
+		}
+	};
+
+	// a fixed capacity ColVec stored inline, with the columns laid out in a byte buffer like a heap buffer
+	let array = {
+		let array_ident=syn::Ident::new(&format!("{ident}ArrayColVec"),ident.span());
+		let cap=fresh_ident(&generics,"CAP");
+		// The byte buffer is sized with size_of, which stable Rust cannot evaluate for a generic struct
+		// in an array length, so a generic struct keeps an array for each column instead.
+		let (storage,storage_init,columns_ref,columns_mut,column_refs,column_muts)=if generic_params.is_empty(){
+			let inline_offsets=field_indices.iter().map(|index|quote!{
+				const { <#struct_ty as ::colvec::fields::StructInfo<#fields_count>>::FIELDS.inline_offset_of(#index, #cap) }
+			}).collect::<Vec<_>>();
+			let column_ptrs=inline_offsets.iter().zip(&field_types).map(|(offset,field_ty)|quote!{columns.add(#offset).cast::<#field_ty>()}).collect::<Vec<_>>();
+			(
+				quote!{buf: ::colvec::inline::InlineBuffer<#struct_ty, { ::core::mem::size_of::<#struct_ty>() }, #cap>},
+				quote!{buf: ::colvec::inline::InlineBuffer::new()},
+				quote!{self.buf.as_ptr()},
+				quote!{self.buf.as_mut_ptr()},
+				column_ptrs.clone(),
+				column_ptrs,
+			)
+		}else{
+			(
+				quote!{columns: (#([::core::mem::MaybeUninit<#field_types>; #cap],)*)},
+				quote!{columns: (#([const { ::core::mem::MaybeUninit::<#field_types>::uninit() }; #cap],)*)},
+				quote!{&self.columns},
+				quote!{&mut self.columns},
+				tuple_indices.iter().zip(&field_types).map(|(index,field_ty)|quote!{columns.#index.as_ptr().cast::<#field_ty>()}).collect(),
+				tuple_indices.iter().zip(&field_types).map(|(index,field_ty)|quote!{columns.#index.as_mut_ptr().cast::<#field_ty>()}).collect(),
+			)
+		};
+		// every column pointer is derived from a single borrow of the storage
+		let array_ptrs_init=quote!{
+			let columns = #columns_ref;
+			(#(unsafe { ::core::ptr::NonNull::new_unchecked(#column_refs.cast_mut()) },)*)
+		};
+		let array_ptrs_mut_init=quote!{
+			let columns = #columns_mut;
+			(#(unsafe { ::core::ptr::NonNull::new_unchecked(#column_muts) },)*)
+		};
+		quote! {
+			/// A ColVec with a fixed capacity of `CAP` rows, which are stored inline
+			/// at the offsets of the columns and never allocate.
+			#vis struct #array_ident<#(#generic_params,)* const #cap: usize> #where_clause {
+				#storage,
+				len: usize,
+			}
+
+			impl<#(#generic_params,)* const #cap: usize> #array_ident<#(#generic_args,)* #cap> #where_clause {
+				#[inline]
+				#[must_use]
+				pub const fn new() -> Self {
+					Self {
+						#storage_init,
+						len: 0,
+					}
+				}
+				#[inline]
+				pub const fn len(&self) -> usize {
+					self.len
+				}
+				#[inline]
+				pub const fn is_empty(&self) -> bool {
+					self.len == 0
+				}
+				#[inline]
+				pub const fn capacity(&self) -> usize {
+					#cap
+				}
+				#[inline]
+				pub const fn is_full(&self) -> bool {
+					self.len == #cap
+				}
+				/// Gets a pointer to the start of every column, for reading.
+				#[inline]
+				#[allow(clippy::unused_unit)]
+				fn column_ptrs(&self) -> #iter_ptrs {
+					#array_ptrs_init
+				}
+				/// Gets a pointer to the start of every column, for writing.
+				#[inline]
+				#[allow(clippy::unused_unit)]
+				fn column_ptrs_mut(&mut self) -> #iter_ptrs {
+					#array_ptrs_mut_init
+				}
+				/// Appends a row.
+				///
+				/// # Panics
+				///
+				/// Panics if the ArrayColVec is full.
+				#[track_caller]
+				pub fn push(&mut self, value: #struct_ty) {
+					if self.try_push(value).is_err() {
+						panic!("ArrayColVec is full (capacity is {})", #cap);
+					}
+				}
+				/// Appends a row, returning it back if the ArrayColVec is full.
+				pub fn try_push(&mut self, value: #struct_ty) -> Result<(), #struct_ty> {
+					let len = self.len;
+					if len == #cap {
+						return Err(value);
+					}
+					let ptrs = self.column_ptrs_mut();
+					unsafe {
+						#(::core::ptr::write(ptrs.#tuple_indices.as_ptr().add(len), value.#field_members);)*
+					}
+					self.len = len + 1;
+					Ok(())
+				}
+				#[inline]
+				pub fn pop(&mut self) -> Option<#struct_ty> {
+					if self.len == 0 {
+						return None;
+					}
+					self.len -= 1;
+					let (ptrs, index) = (self.column_ptrs(), self.len);
+					Some(unsafe { #ident { #(#field_members: ::core::ptr::read(ptrs.#tuple_indices.as_ptr().add(index)),)* } })
+				}
+				/// Removes the row at `index` and moves the last row into its place.
+				///
+				/// # Panics
+				///
+				/// Panics if `index` is out of bounds.
+				#[track_caller]
+				pub fn swap_remove(&mut self, index: usize) -> #struct_ty {
+					let len = self.len;
+					if index >= len {
+						panic!("swap_remove index (is {index}) should be < len (is {len})");
+					}
+					let ptrs = self.column_ptrs_mut();
+					self.len = len - 1;
+					unsafe {
+						let value = #ident { #(#field_members: ::core::ptr::read(ptrs.#tuple_indices.as_ptr().add(index)),)* };
+						#(::core::ptr::copy(ptrs.#tuple_indices.as_ptr().add(len - 1), ptrs.#tuple_indices.as_ptr().add(index), 1);)*
+						value
+					}
+				}
+				pub fn truncate(&mut self, len: usize) {
+					let old_len = self.len;
+					if len >= old_len {
+						return;
+					}
+					// Shrink the length first, in case a value panics while dropping.
+					self.len = len;
+					let ptrs = self.column_ptrs_mut();
+					// if one column panics while dropping, the remaining columns are still dropped
+					unsafe {
+						let _columns = (#(::colvec::column::DropColumn::new(ptrs.#tuple_indices.as_ptr().add(len), old_len - len),)*);
+					}
+				}
+				#[inline]
+				pub fn clear(&mut self) {
+					self.truncate(0);
+				}
+				#[inline]
+				pub fn get(&self, index: usize) -> Option<#ref_ident<'_ #(, #generic_args)*>> {
+					if index < self.len {
+						let ptrs = self.column_ptrs();
+						Some(unsafe { #ptrs_ref })
+					} else {
+						None
+					}
+				}
+				#[inline]
+				pub fn get_mut(&mut self, index: usize) -> Option<#mut_ident<'_ #(, #generic_args)*>> {
+					if index < self.len {
+						let ptrs = self.column_ptrs_mut();
+						Some(unsafe { #ptrs_mut })
+					} else {
+						None
+					}
+				}
+				#[inline]
+				pub fn iter(&self) -> #iter_ident<'_ #(, #generic_args)*> {
+					#iter_ident {
+						ptrs: self.column_ptrs(),
+						start: 0,
+						end: self.len,
+						_marker: ::core::marker::PhantomData,
+					}
+				}
+				#[inline]
+				pub fn iter_mut(&mut self) -> #iter_mut_ident<'_ #(, #generic_args)*> {
+					#iter_mut_ident {
+						ptrs: self.column_ptrs_mut(),
+						start: 0,
+						end: self.len,
+						_marker: ::core::marker::PhantomData,
+					}
+				}
+				#[inline]
+				pub fn columns(&self) -> #columns_ident<'_ #(, #generic_args)*> {
+					let ptrs = self.column_ptrs();
+					let len = self.len;
+					unsafe { #ptrs_columns }
+				}
+				#[inline]
+				pub fn columns_mut(&mut self) -> #columns_mut_ident<'_ #(, #generic_args)*> {
+					let ptrs = self.column_ptrs_mut();
+					let len = self.len;
+					unsafe { #ptrs_columns_mut }
+				}
+				#(
+					#[inline]
+					#[allow(dead_code)]
+					#field_vis const fn #field_slice_fn_idents(&self) -> &[#field_types] {
+						let columns = #columns_ref;
+						unsafe { ::core::slice::from_raw_parts(#column_refs, self.len) }
+					}
+					#[inline]
+					#[allow(dead_code)]
+					#field_vis const fn #field_slice_mut_fn_idents(&mut self) -> &mut [#field_types] {
+						let columns = #columns_mut;
+						unsafe { ::core::slice::from_raw_parts_mut(#column_muts, self.len) }
+					}
+				)*
+			}
+
+			impl<#(#generic_params,)* const #cap: usize> ::core::default::Default for #array_ident<#(#generic_args,)* #cap> #where_clause {
+				#[inline]
+				fn default() -> Self {
+					Self::new()
+				}
+			}
+
+			impl<#(#generic_params,)* const #cap: usize> ::core::iter::Extend<#struct_ty> for #array_ident<#(#generic_args,)* #cap> #where_clause {
+				/// # Panics
+				///
+				/// Panics if the rows do not fit.
+				#[track_caller]
+				fn extend<#i: ::core::iter::IntoIterator<Item = #struct_ty>>(&mut self, iter: #i) {
+					for value in iter {
+						self.push(value);
+					}
+				}
+			}
+
+			impl<#(#generic_params,)* const #cap: usize> ::core::clone::Clone for #array_ident<#(#generic_args,)* #cap>
+			where #(#where_preds,)*
+				#(#clone_bounds,)*
+			{
+				fn clone(&self) -> Self {
+					let len = self.len;
+					let mut array = Self::new();
+					let (src, dst) = (self.column_ptrs(), array.column_ptrs_mut());
+					// a panicking clone drops the columns which were already cloned
+					let _columns = ::core::mem::ManuallyDrop::new((
+						#(unsafe { ::colvec::column::clone_column(src.#tuple_indices.as_ptr().cast_const(), dst.#tuple_indices.as_ptr(), len) },)*
+					));
+					array.len = len;
+					array
+				}
+			}
+
+			impl<#(#generic_params,)* const #cap: usize> ::core::fmt::Debug for #array_ident<#(#generic_args,)* #cap>
+			where #(#where_preds,)*
+				#(#debug_bounds,)*
+			{
+				fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+					f.debug_list().entries(self.iter()).finish()
+				}
+			}
+
+			impl<#(#generic_params,)* const #cap: usize> Drop for #array_ident<#(#generic_args,)* #cap> #where_clause {
+				fn drop(&mut self) {
+					self.truncate(0);
+				}
+			}
+		}
+	};
+
+	// up to K rows are stored inline, and they are moved to a ColVec on the heap once they overflow
+	let small = {
+		let small_ident=syn::Ident::new(&format!("{ident}SmallColVec"),ident.span());
+		let storage_ident=syn::Ident::new(&format!("{ident}SmallStorage"),ident.span());
+		let array_ident=syn::Ident::new(&format!("{ident}ArrayColVec"),ident.span());
+		let forward=|call:&dyn quote::ToTokens|quote!{
+			match &self.storage {
+				#storage_ident::Inline(array, _) => array.#call,
+				#storage_ident::Heap(colvec) => colvec.#call,
+			}
+		};
+		let forward_mut=|call:&dyn quote::ToTokens|quote!{
+			match &mut self.storage {
+				#storage_ident::Inline(array, _) => array.#call,
+				#storage_ident::Heap(colvec) => colvec.#call,
+			}
+		};
+		let forward_len=forward(&quote!{len()});
+		let forward_capacity=forward(&quote!{capacity()});
+		let forward_pop=forward_mut(&quote!{pop()});
+		let forward_truncate=forward_mut(&quote!{truncate(len)});
+		let forward_swap_remove=forward_mut(&quote!{swap_remove(index)});
+		let forward_get=forward(&quote!{get(index)});
+		let forward_get_mut=forward_mut(&quote!{get_mut(index)});
+		let forward_iter=forward(&quote!{iter()});
+		let forward_iter_mut=forward_mut(&quote!{iter_mut()});
+		let forward_columns=forward(&quote!{columns()});
+		let forward_columns_mut=forward_mut(&quote!{columns_mut()});
+		let forward_slices=field_slice_fn_idents.iter().map(|slice_fn|forward(&quote!{#slice_fn()})).collect::<Vec<_>>();
+		let forward_slices_mut=field_slice_mut_fn_idents.iter().map(|slice_fn|forward_mut(&quote!{#slice_fn()})).collect::<Vec<_>>();
+
+		#[cfg_attr(not(feature = "std"), expect(unused_mut))]
+		let mut small: syn::ItemStruct = syn::parse_quote!{
+			/// A ColVec which stores up to `K` rows inline, and moves them to the heap
+			/// once there are more rows than that.
+			#vis struct #small_ident<#(#generic_params,)* const #k: usize, #a: ::colvec::alloc::Allocator> #where_clause {
+				storage: #storage_ident<#(#generic_args,)* #k, #a>,
+			}
+		};
+		#[cfg(feature = "std")]
+		default_global(&mut small.generics);
+
+		#[cfg(feature = "std")]
+		let small_global = quote! {
+			impl<#(#generic_params,)* const #k: usize> #small_ident<#(#generic_args,)* #k, ::colvec::alloc::Global> #where_clause {
+				#[inline]
+				#[must_use]
+				pub const fn new() -> Self {
+					Self::new_in(::colvec::alloc::Global)
+				}
+				#[inline]
+				#[must_use]
+				#[track_caller]
+				pub fn with_capacity(capacity: usize) -> Self {
+					Self::with_capacity_in(capacity, ::colvec::alloc::Global)
+				}
+			}
+		};
+		#[cfg(not(feature = "std"))]
+		let small_global = quote!{};
+
+		quote! {
+			#small
+
+			enum #storage_ident<#(#generic_params,)* const #k: usize, #a: ::colvec::alloc::Allocator> #where_clause {
+				// the allocator is kept for when the rows move to the heap
+				Inline(#array_ident<#(#generic_args,)* #k>, #a),
+				Heap(#colvec_ident<#(#generic_args,)* #a>),
+			}
+
+			#small_global
+
+			impl<#(#generic_params,)* const #k: usize, #a: ::colvec::alloc::Allocator> #small_ident<#(#generic_args,)* #k, #a> #where_clause {
+				#[inline]
+				pub const fn new_in(alloc: #a) -> Self {
+					Self { storage: #storage_ident::Inline(#array_ident::new(), alloc) }
+				}
+				/// Makes a SmallColVec which stores its rows on the heap from the start
+				/// if `capacity` is more than `K`.
+				#[inline]
+				#[track_caller]
+				pub fn with_capacity_in(capacity: usize, alloc: #a) -> Self {
+					if capacity <= #k {
+						Self::new_in(alloc)
+					} else {
+						Self { storage: #storage_ident::Heap(#colvec_ident::with_capacity_in(capacity, alloc)) }
+					}
+				}
+				/// Whether the rows have moved to the heap.
+				#[inline]
+				pub const fn spilled(&self) -> bool {
+					matches!(self.storage, #storage_ident::Heap(_))
+				}
+				#[inline]
+				pub fn len(&self) -> usize {
+					#forward_len
+				}
+				#[inline]
+				pub fn is_empty(&self) -> bool {
+					self.len() == 0
+				}
+				#[inline]
+				pub fn capacity(&self) -> usize {
+					#forward_capacity
+				}
+				#[inline]
+				pub fn allocator(&self) -> &#a {
+					match &self.storage {
+						#storage_ident::Inline(_, alloc) => alloc,
+						#storage_ident::Heap(colvec) => colvec.allocator(),
+					}
+				}
+				#[track_caller]
+				pub fn reserve(&mut self, additional: usize) {
+					match &mut self.storage {
+						#storage_ident::Inline(array, _) => if #k - array.len() < additional {
+							self.spill(additional);
+						},
+						#storage_ident::Heap(colvec) => colvec.reserve(additional),
+					}
+				}
+				/// Moves the rows to the heap with room for `additional` more rows,
+				/// if they are still stored inline.
+				#[cold]
+				#[track_caller]
+				fn spill(&mut self, additional: usize) {
+					// SAFETY: the storage is read out and replaced by an empty ColVec,
+					// which cannot panic, before anything else is done with it.
+					let (mut array, mut colvec) = match unsafe { ::core::ptr::read(&self.storage) } {
+						#storage_ident::Inline(array, alloc) => {
+							unsafe { ::core::ptr::write(&mut self.storage, #storage_ident::Heap(#colvec_ident::new_in(alloc))) };
+							let #storage_ident::Heap(colvec) = &mut self.storage else {
+								unreachable!()
+							};
+							(array, colvec)
+						},
+						heap => {
+							::core::mem::forget(heap);
+							return;
+						},
+					};
+					let len = array.len();
+					// if this panics, the rows are dropped with the array
+					colvec.reserve(len.checked_add(additional).expect("capacity overflow"));
+					let (src, dst) = (array.column_ptrs(), colvec.column_ptrs());
+					unsafe {
+						#(::core::ptr::copy_nonoverlapping(src.#tuple_indices.as_ptr().cast_const(), dst.#tuple_indices.as_ptr(), len);)*
+					}
+					// the rows belong to the ColVec now
+					array.len = 0;
+					colvec.len = len;
+				}
+				#[inline]
+				#[track_caller]
+				pub fn push(&mut self, value: #struct_ty) {
+					let value = match &mut self.storage {
+						#storage_ident::Inline(array, _) => match array.try_push(value) {
+							Ok(()) => return,
+							Err(value) => value,
+						},
+						#storage_ident::Heap(colvec) => return colvec.push(value),
+					};
+					self.spill(1);
+					if let #storage_ident::Heap(colvec) = &mut self.storage {
+						colvec.push(value);
+					}
+				}
+				#[inline]
+				pub fn pop(&mut self) -> Option<#struct_ty> {
+					#forward_pop
+				}
+				#[track_caller]
+				pub fn swap_remove(&mut self, index: usize) -> #struct_ty {
+					#forward_swap_remove
+				}
+				#[inline]
+				pub fn truncate(&mut self, len: usize) {
+					#forward_truncate
+				}
+				#[inline]
+				pub fn clear(&mut self) {
+					self.truncate(0);
+				}
+				#[inline]
+				pub fn get(&self, index: usize) -> Option<#ref_ident<'_ #(, #generic_args)*>> {
+					#forward_get
+				}
+				#[inline]
+				pub fn get_mut(&mut self, index: usize) -> Option<#mut_ident<'_ #(, #generic_args)*>> {
+					#forward_get_mut
+				}
+				#[inline]
+				pub fn iter(&self) -> #iter_ident<'_ #(, #generic_args)*> {
+					#forward_iter
+				}
+				#[inline]
+				pub fn iter_mut(&mut self) -> #iter_mut_ident<'_ #(, #generic_args)*> {
+					#forward_iter_mut
+				}
+				#[inline]
+				pub fn columns(&self) -> #columns_ident<'_ #(, #generic_args)*> {
+					#forward_columns
+				}
+				#[inline]
+				pub fn columns_mut(&mut self) -> #columns_mut_ident<'_ #(, #generic_args)*> {
+					#forward_columns_mut
+				}
+				#(
+					#[inline]
+					#[allow(dead_code)]
+					#field_vis fn #field_slice_fn_idents(&self) -> &[#field_types] {
+						#forward_slices
+					}
+					#[inline]
+					#[allow(dead_code)]
+					#field_vis fn #field_slice_mut_fn_idents(&mut self) -> &mut [#field_types] {
+						#forward_slices_mut
+					}
+				)*
+				/// Converts into a ColVec, moving the rows to the heap if they are still stored inline.
+				#[track_caller]
+				pub fn into_colvec(mut self) -> #colvec_ident<#(#generic_args,)* #a> {
+					self.spill(0);
+					match self.storage {
+						#storage_ident::Heap(colvec) => colvec,
+						#storage_ident::Inline(..) => unreachable!(),
+					}
+				}
+			}
+
+			impl<#(#generic_params,)* const #k: usize, #a: ::colvec::alloc::Allocator + ::core::default::Default> ::core::default::Default for #small_ident<#(#generic_args,)* #k, #a> #where_clause {
+				#[inline]
+				fn default() -> Self {
+					Self::new_in(#a::default())
+				}
+			}
+
+			impl<#(#generic_params,)* const #k: usize, #a: ::colvec::alloc::Allocator> ::core::iter::Extend<#struct_ty> for #small_ident<#(#generic_args,)* #k, #a> #where_clause {
+				#[track_caller]
+				fn extend<#i: ::core::iter::IntoIterator<Item = #struct_ty>>(&mut self, iter: #i) {
+					let iter = iter.into_iter();
+					self.reserve(iter.size_hint().0);
+					for value in iter {
+						self.push(value);
+					}
+				}
+			}
+
+			impl<#(#generic_params,)* const #k: usize, #a: ::colvec::alloc::Allocator + ::core::clone::Clone> ::core::clone::Clone for #small_ident<#(#generic_args,)* #k, #a>
+			where #(#where_preds,)*
+				#(#clone_bounds,)*
+			{
+				#[track_caller]
+				fn clone(&self) -> Self {
+					let storage = match &self.storage {
+						#storage_ident::Inline(array, alloc) => #storage_ident::Inline(array.clone(), alloc.clone()),
+						#storage_ident::Heap(colvec) => #storage_ident::Heap(colvec.clone()),
+					};
+					Self { storage }
+				}
+			}
+
+			impl<#(#generic_params,)* const #k: usize, #a: ::colvec::alloc::Allocator> ::core::fmt::Debug for #small_ident<#(#generic_args,)* #k, #a>
+			where #(#where_preds,)*
+				#(#debug_bounds,)*
+			{
+				fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+					f.debug_list().entries(self.iter()).finish()
+				}
+			}
+		}
+	};
+
+	let drop = quote! {
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> Drop for #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			fn drop(&mut self) {
+				// RawColVec handles deallocation afterwards.
+				unsafe { self.drop_rows(0, self.len) }
+			}
+		}
+	};
+
+	let mut output=quote! {
+		#struct_info
+
+		#row_refs
+		#iter
+		#columns
+		#debug_views
+		#array
+	};
+
+	// everything backed by a heap allocation
+	if cfg!(feature = "alloc"){
+		output.extend(quote! {
+			#colvec
+
+			#impls
+			#raw_parts
+			#field_access
+			#row_access
+			#colvec_iter
+			#col_slice
+			#colvec_columns
+			#columnar
+			#sort
+			#remove
+			#drain
+			#conversions
+			#traits
+			#insert
+			#drop
+			#small
+		});
+	}
+
+	#[cfg(feature = "std")]
+	output.extend(global);
+	#[cfg(feature = "std")]
+	output.extend(global_raw_parts);
+	#[cfg(feature = "std")]
+	output.extend(global_conversions);
+	#[cfg(feature = "std")]
+	output.extend(columns_owned);
+
+	output.into()
+}
+
+// An enum is stored as two columns, like Zig's MultiArrayList of a tagged union:
+// a tag column of a generated fieldless enum, and a payload column of a generated union
+// holding each variant's fields as a tuple.
+#[cfg_attr(test, expect(clippy::useless_conversion))]
+fn derive_enum(ident:syn::Ident,vis:syn::Visibility,mut generics:syn::Generics,variants:syn::punctuated::Punctuated<syn::Variant,syn::Token![,]>)->TokenStream{
+	if !cfg!(feature = "alloc"){
+		return syn::Error::new(ident.span(),"ColVec can only be derived for an enum with the alloc feature of colvec").to_compile_error().into();
+	}
+	if variants.is_empty(){
+		return syn::Error::new(ident.span(),"ColVec cannot be derived for an enum with no variants").to_compile_error().into();
+	}
+	// the fields of a variant are stored together in the payload, so they have no accessors to name
+	if let Err(error)=variants.iter().flat_map(|variant|&variant.fields).try_for_each(|field|reject_attributes(&field.attrs)){
+		return error.to_compile_error().into();
+	}
+	let colvec_ident=syn::Ident::new(&format!("{ident}ColVec"),ident.span());
+	let tag_ident=syn::Ident::new(&format!("{ident}Tag"),ident.span());
+	let payload_ident=syn::Ident::new(&format!("{ident}Payload"),ident.span());
+	let ref_ident=syn::Ident::new(&format!("{ident}Ref"),ident.span());
+	let mut_ident=syn::Ident::new(&format!("{ident}Mut"),ident.span());
+	let iter_ident=syn::Ident::new(&format!("{ident}Iter"),ident.span());
+	let iter_mut_ident=syn::Ident::new(&format!("{ident}IterMut"),ident.span());
+
+	strip_defaults(&mut generics);
+	let (impl_generics,ty_generics,where_clause)=generics.split_for_impl();
+	let enum_ty:syn::Type=syn::parse_quote!{#ident #ty_generics};
+	let generic_params=generics.params.iter().collect::<Vec<_>>();
+	let generic_args=generic_args(&generics);
+	let where_preds=where_clause.iter().flat_map(|where_clause|&where_clause.predicates).collect::<Vec<_>>();
+	let payload_ty:syn::Type=syn::parse_quote!{#payload_ident #ty_generics};
+	let a=fresh_ident(&generics,"A");
+	let i=fresh_ident(&generics,"I");
+	let lt_a=fresh_lifetime(&generics,"a");
+
+	let variant_idents=variants.iter().map(|variant|&variant.ident).collect::<Vec<_>>();
+	let variant_members=variants.iter().map(|variant|variant.fields.members().collect::<Vec<_>>()).collect::<Vec<_>>();
+	let variant_bindings=variants.iter().map(|variant|{
+		(0..variant.fields.len()).map(|index|quote::format_ident!("field{index}")).collect::<Vec<_>>()
+	}).collect::<Vec<_>>();
+	let variant_types=variants.iter().map(|variant|->syn::Type{
+		let field_types=variant.fields.iter().map(|field|&field.ty);
+		syn::parse_quote!{(#(#field_types,)*)}
+	}).collect::<Vec<_>>();
+	let variant_names=match variants.iter().map(variant_name).collect::<syn::Result<Vec<_>>>(){
+		Ok(variant_names)=>variant_names,
+		Err(error)=>return error.to_compile_error().into(),
+	};
+	let get_fn_idents=variant_names.iter().map(|name|quote::format_ident!("get_{name}",span=name.span())).collect::<Vec<_>>();
+	let get_mut_fn_idents=variant_names.iter().map(|name|quote::format_ident!("get_{name}_mut",span=name.span())).collect::<Vec<_>>();
+	// the views of a row mirror the enum's variants, with a reference in place of each field
+	let view_variants=|mutability:Option<syn::Token![mut]>|variants.iter().map(|variant|{
+		let mut variant=variant.clone();
+		variant.attrs.clear();
+		variant.discriminant=None;
+		for field in &mut variant.fields{
+			let ty=&field.ty;
+			field.ty=syn::parse_quote!{&#lt_a #mutability #ty};
+		}
+		variant
+	}).collect::<Vec<_>>();
+	let ref_variants=view_variants(None);
+	let mut_variants=view_variants(Some(Default::default()));
+	// an enum with no fields would leave the lifetime of the views unused
+	let has_fields=variants.iter().any(|variant|!variant.fields.is_empty());
+	let view_marker=(!has_fields).then(||quote!{
+		#[doc(hidden)]
+		_Marker(::core::convert::Infallible, ::core::marker::PhantomData<&#lt_a ()>),
+	});
+	let field_types=variants.iter().flat_map(|variant|variant.fields.iter().map(|field|field.ty.clone())).collect::<Vec<_>>();
+	let debug_bounds=field_bounds(&field_types,syn::parse_quote!{::core::fmt::Debug});
+	let clone_bounds=field_bounds(&field_types,syn::parse_quote!{::core::clone::Clone});
+	let sync_bounds=field_bounds(&field_types,syn::parse_quote!{::core::marker::Sync});
+	let send_bounds=field_bounds(&field_types,syn::parse_quote!{::core::marker::Send});
+	// build a row view out of the column pointers `tags` and `payloads` at `index`
+	let ptrs_ref=quote!{{
+		let payload = payloads.add(index);
+		match *tags.add(index) {
+			#(
+				#tag_ident::#variant_idents => {
+					let (#(#variant_bindings,)*) = &*(*payload).#variant_idents;
+					#ref_ident::#variant_idents { #(#variant_members: #variant_bindings,)* }
+				},
+			)*
+		}
+	}};
+	let ptrs_mut=quote!{{
+		let payload = payloads.add(index);
+		match *tags.add(index) {
+			#(
+				#tag_ident::#variant_idents => {
+					let (#(#variant_bindings,)*) = &mut *(*payload).#variant_idents;
+					#mut_ident::#variant_idents { #(#variant_members: #variant_bindings,)* }
+				},
+			)*
+		}
+	}};
+	// the views print like the enum would
+	let debug_variants=variants.iter().zip(&variant_bindings).map(|(variant,bindings)|{
+		let variant_string=variant.ident.to_string();
+		match &variant.fields{
+			syn::Fields::Named(_)=>{
+				let field_ident_strings=variant.fields.iter().map(|field|field.ident.as_ref().unwrap().to_string());
+				quote!{
+					f.debug_struct(#variant_string)
+						#(.field(#field_ident_strings, #bindings))*
+						.finish()
+				}
+			},
+			syn::Fields::Unnamed(_)=>quote!{
+				f.debug_tuple(#variant_string)
+					#(.field(#bindings))*
+					.finish()
+			},
+			syn::Fields::Unit=>quote!{f.write_str(#variant_string)},
+		}
+	}).collect::<Vec<_>>();
+	let debug_marker=(!has_fields).then(||quote!{
+		Self::_Marker(never, _) => match *never {},
+	});
+	let debug_row=quote!{
+		fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+			match self {
+				#(
+					Self::#variant_idents { #(#variant_members: #variant_bindings,)* } => #debug_variants,
+				)*
+				#debug_marker
+			}
+		}
+	};
+
+	#[cfg_attr(not(feature = "std"), expect(unused_mut))]
+	let mut colvec: syn::ItemStruct = syn::parse_quote!{
+		#vis struct #colvec_ident<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #where_clause {
+			buf: ::colvec::raw::RawColVec<2, #enum_ty, #a>,
+			len: usize,
+		}
+	};
+
+	#[cfg(feature = "std")]
+	default_global(&mut colvec.generics);
+
+	let columns = quote! {
+		#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+		#vis enum #tag_ident {
+			#(#variant_idents,)*
+		}
+
+		/// A borrowed row, with a shared reference to each field of its variant.
+		#vis enum #ref_ident<#lt_a, #(#generic_params,)*> #where_clause {
+			#(#ref_variants,)*
+			#view_marker
+		}
+
+		// not derived, which would require the enum's type parameters to be Clone
+		impl #impl_generics ::core::clone::Clone for #ref_ident<'_ #(, #generic_args)*> #where_clause {
+			#[inline]
+			fn clone(&self) -> Self {
+				*self
+			}
+		}
+		impl #impl_generics ::core::marker::Copy for #ref_ident<'_ #(, #generic_args)*> #where_clause {}
+
+		/// A borrowed row, with a mutable reference to each field of its variant.
+		#vis enum #mut_ident<#lt_a, #(#generic_params,)*> #where_clause {
+			#(#mut_variants,)*
+			#view_marker
+		}
+
+		impl #impl_generics ::core::fmt::Debug for #ref_ident<'_ #(, #generic_args)*>
+		where #(#where_preds,)*
+			#(#debug_bounds,)*
+		{
+			#debug_row
+		}
+
+		impl #impl_generics ::core::fmt::Debug for #mut_ident<'_ #(, #generic_args)*>
+		where #(#where_preds,)*
+			#(#debug_bounds,)*
+		{
+			#debug_row
+		}
+
+		#[allow(non_snake_case)]
+		union #payload_ident<#(#generic_params),*> #where_clause {
+			#(#variant_idents: ::core::mem::ManuallyDrop<#variant_types>,)*
+		}
+	};
+
+	#[cfg(feature = "std")]
+	let global = quote! {
+		impl #impl_generics #colvec_ident<#(#generic_args,)* ::colvec::alloc::Global> #where_clause {
+			#[inline]
+			#[must_use]
+			pub const fn new() -> Self {
+				Self { buf: ::colvec::raw::RawColVec::new_in(::colvec::alloc::Global), len: 0 }
+			}
+			#[inline]
+			#[must_use]
+			#[track_caller]
+			pub fn with_capacity(capacity: usize) -> Self {
+				Self::with_capacity_in(capacity, ::colvec::alloc::Global)
+			}
+		}
+
+		impl #impl_generics ::core::iter::FromIterator<#enum_ty> for #colvec_ident<#(#generic_args,)* ::colvec::alloc::Global> #where_clause {
+			#[inline]
+			#[track_caller]
+			fn from_iter<#i: ::core::iter::IntoIterator<Item = #enum_ty>>(iter: #i) -> Self {
+				let mut colvec = Self::new();
+				colvec.extend(iter);
+				colvec
+			}
+		}
+	};
+
+	// the tag and payload columns are sized and aligned separately
+	let struct_info = quote! {
+		impl #impl_generics ::colvec::fields::StructInfo<2> for #enum_ty #where_clause {
+			const LAYOUT: ::core::alloc::Layout = unsafe {
+				let size = Self::FIELDS.size();
+				let align = if align_of::<#tag_ident>() < align_of::<#payload_ty>() {
+					align_of::<#payload_ty>()
+				} else {
+					align_of::<#tag_ident>()
+				};
+				::core::alloc::Layout::from_size_align_unchecked(size, align)
+			};
+			const FIELDS: ::colvec::fields::Fields<2> = ::colvec::fields::Fields::from_layouts([
+				::core::alloc::Layout::new::<#tag_ident>(),
+				::core::alloc::Layout::new::<#payload_ty>(),
+			]);
+		}
+	};
+
+	let impls = quote! {
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			#[inline]
+			pub const fn new_in(alloc: #a) -> Self {
+				Self { buf: ::colvec::raw::RawColVec::new_in(alloc), len: 0 }
+			}
+			#[inline]
+			#[track_caller]
+			pub fn with_capacity_in(capacity: usize, alloc: #a) -> Self {
+				Self { buf: ::colvec::raw::RawColVec::with_capacity_in(capacity, alloc), len: 0 }
+			}
+			#[inline]
+			pub const fn capacity(&self) -> usize {
+				self.buf.capacity()
+			}
+			#[track_caller]
+			pub fn reserve(&mut self, additional: usize) {
+				self.buf.reserve(self.len, additional);
+			}
+			#[inline]
+			pub fn allocator(&self) -> &#a {
+				self.buf.allocator()
+			}
+			#[inline]
+			pub const fn len(&self) -> usize {
+				self.len
+			}
+			#[inline]
+			pub const fn is_empty(&self) -> bool {
+				self.len == 0
+			}
+			#[inline]
+			const fn tags_ptr(&self) -> *mut #tag_ident {
+				self.buf.field_ptr(0).cast()
+			}
+			#[inline]
+			const fn payloads_ptr(&self) -> *mut #payload_ty {
+				self.buf.field_ptr(1).cast()
+			}
+			/// The variant of every row.
+			#[inline]
+			pub const fn tags_slice(&self) -> &[#tag_ident] {
+				unsafe { ::core::slice::from_raw_parts(self.tags_ptr(), self.len) }
+			}
+			/// Splits `value` into its tag and payload and writes them into the row at `index`.
+			///
+			/// # Safety
+			///
+			/// `index` must be less than the capacity, and the row must not be initialized.
+			#[inline]
+			unsafe fn write_row(&mut self, index: usize, value: #enum_ty) {
+				let (tag, payload) = match value {
+					#(
+						#ident::#variant_idents { #(#variant_members: #variant_bindings,)* } => (
+							#tag_ident::#variant_idents,
+							#payload_ident { #variant_idents: ::core::mem::ManuallyDrop::new((#(#variant_bindings,)*)) },
+						),
+					)*
+				};
+				unsafe {
+					::core::ptr::write(self.tags_ptr().add(index), tag);
+					::core::ptr::write(self.payloads_ptr().add(index), payload);
+				}
+			}
+			/// Reads the row at `index` back into the enum.
+			///
+			/// # Safety
+			///
+			/// `index` must be less than `self.len`, and the row must not be used again.
+			#[inline]
+			unsafe fn read_row(&self, index: usize) -> #enum_ty {
+				unsafe {
+					let payload = ::core::ptr::read(self.payloads_ptr().add(index));
+					match *self.tags_ptr().add(index) {
+						#(
+							#tag_ident::#variant_idents => {
+								let (#(#variant_bindings,)*) = ::core::mem::ManuallyDrop::into_inner(payload.#variant_idents);
+								#ident::#variant_idents { #(#variant_members: #variant_bindings,)* }
+							},
+						)*
+					}
+				}
+			}
+			/// Drops the payload of one row according to its tag.
+			///
+			/// # Safety
+			///
+			/// `payload` must be initialized as the variant of `tag`, and must not be used again.
+			unsafe fn drop_payload(tag: &#tag_ident, payload: *mut #payload_ty) {
+				unsafe {
+					match tag {
+						#(
+							#tag_ident::#variant_idents => ::core::mem::ManuallyDrop::drop(&mut (*payload).#variant_idents),
+						)*
+					}
+				}
+			}
+			/// Drops `count` rows in place starting at `start`.
+			///
+			/// # Safety
+			///
+			/// The rows must be initialized and must not be used again.
+			#[inline]
+			unsafe fn drop_rows(&mut self, start: usize, count: usize) {
+				unsafe {
+					let _payloads = ::colvec::column::DropPayloads::new(
+						self.tags_ptr().add(start),
+						self.payloads_ptr().add(start),
+						count,
+						Self::drop_payload,
+					);
+				}
+			}
+			pub fn push(&mut self, value: #enum_ty) {
+				// Inform codegen that the length does not change across grow_one().
+				let len = self.len;
+				if len == self.buf.capacity() {
+					self.buf.grow_one();
+				}
+				unsafe {
+					self.write_row(len, value);
+				}
+				self.len = len + 1;
+			}
+			#[inline]
+			pub fn pop(&mut self) -> Option<#enum_ty> {
+				if self.len == 0 {
+					None
+				} else {
+					unsafe {
+						self.len -= 1;
+						Some(self.read_row(self.len))
+					}
+				}
+			}
+			pub fn truncate(&mut self, len: usize) {
+				if len > self.len {
+					return;
+				}
+				let remaining_len = self.len - len;
+				// Shrink the length first, in case a payload panics while dropping.
+				self.len = len;
+				unsafe { self.drop_rows(len, remaining_len) };
+			}
+			#[inline]
+			pub fn clear(&mut self) {
+				self.truncate(0);
+			}
+			#[inline]
+			pub fn get(&self, index: usize) -> Option<#ref_ident<'_ #(, #generic_args)*>> {
+				if index < self.len {
+					let (tags, payloads) = (self.tags_ptr(), self.payloads_ptr());
+					Some(unsafe { #ptrs_ref })
+				} else {
+					None
+				}
+			}
+			#[inline]
+			pub fn get_mut(&mut self, index: usize) -> Option<#mut_ident<'_ #(, #generic_args)*>> {
+				if index < self.len {
+					let (tags, payloads) = (self.tags_ptr(), self.payloads_ptr());
+					Some(unsafe { #ptrs_mut })
+				} else {
+					None
+				}
+			}
+			#[inline]
+			pub fn iter(&self) -> #iter_ident<'_ #(, #generic_args)*> {
+				#iter_ident {
+					tags: self.tags_ptr(),
+					payloads: self.payloads_ptr(),
+					start: 0,
+					end: self.len,
+					_marker: ::core::marker::PhantomData,
+				}
+			}
+			#[inline]
+			pub fn iter_mut(&mut self) -> #iter_mut_ident<'_ #(, #generic_args)*> {
+				#iter_mut_ident {
+					tags: self.tags_ptr(),
+					payloads: self.payloads_ptr(),
+					start: 0,
+					end: self.len,
+					_marker: ::core::marker::PhantomData,
+				}
+			}
+			#(
+				/// The fields of the row at `index` as a tuple, if the row is this variant.
+				#[inline]
+				pub fn #get_fn_idents(&self, index: usize) -> Option<&#variant_types> {
+					if index < self.len && ::core::matches!(self.tags_slice()[index], #tag_ident::#variant_idents) {
+						Some(unsafe { &*(*self.payloads_ptr().add(index)).#variant_idents })
+					} else {
+						None
+					}
+				}
+				/// The fields of the row at `index` as a mutable tuple, if the row is this variant.
+				#[inline]
+				pub fn #get_mut_fn_idents(&mut self, index: usize) -> Option<&mut #variant_types> {
+					if index < self.len && ::core::matches!(self.tags_slice()[index], #tag_ident::#variant_idents) {
+						Some(unsafe { &mut *(*self.payloads_ptr().add(index)).#variant_idents })
+					} else {
+						None
+					}
+				}
+			)*
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::Extend<#enum_ty> for #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			#[track_caller]
+			fn extend<#i: ::core::iter::IntoIterator<Item = #enum_ty>>(&mut self, iter: #i) {
+				let iter = iter.into_iter();
+				let (lower, _) = iter.size_hint();
+				self.reserve(lower);
+				for value in iter {
+					self.push(value);
+				}
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> Drop for #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			fn drop(&mut self) {
+				// RawColVec handles deallocation afterwards.
+				unsafe { self.drop_rows(0, self.len) }
+			}
+		}
+	};
+
+	let iter = quote! {
+		/// An iterator over borrowed rows.
+		#vis struct #iter_ident<#lt_a, #(#generic_params,)*> #where_clause {
+			tags: *mut #tag_ident,
+			payloads: *mut #payload_ty,
+			start: usize,
+			end: usize,
+			_marker: ::core::marker::PhantomData<&#lt_a #enum_ty>,
+		}
+
+		/// An iterator over mutably borrowed rows.
+		#vis struct #iter_mut_ident<#lt_a, #(#generic_params,)*> #where_clause {
+			tags: *mut #tag_ident,
+			payloads: *mut #payload_ty,
+			start: usize,
+			end: usize,
+			_marker: ::core::marker::PhantomData<&#lt_a mut #enum_ty>,
+		}
+
+		// The raw column pointers opt out of the auto traits, so they are restored with the
+		// same bounds as the references which the iterators hand out.
+		unsafe impl #impl_generics Send for #iter_ident<'_ #(, #generic_args)*> where #(#where_preds,)* #(#sync_bounds,)* {}
+		unsafe impl #impl_generics Sync for #iter_ident<'_ #(, #generic_args)*> where #(#where_preds,)* #(#sync_bounds,)* {}
+		unsafe impl #impl_generics Send for #iter_mut_ident<'_ #(, #generic_args)*> where #(#where_preds,)* #(#send_bounds,)* {}
+		unsafe impl #impl_generics Sync for #iter_mut_ident<'_ #(, #generic_args)*> where #(#where_preds,)* #(#sync_bounds,)* {}
+
+		impl #impl_generics Clone for #iter_ident<'_ #(, #generic_args)*> #where_clause {
+			#[inline]
+			fn clone(&self) -> Self {
+				Self { ..*self }
+			}
+		}
+
+		impl<#lt_a, #(#generic_params,)*> ::core::iter::Iterator for #iter_ident<#lt_a #(, #generic_args)*> #where_clause {
+			type Item = #ref_ident<#lt_a #(, #generic_args)*>;
+			#[inline]
+			fn next(&mut self) -> Option<#ref_ident<#lt_a #(, #generic_args)*>> {
+				if self.start == self.end {
+					None
+				} else {
+					let index = self.start;
+					self.start += 1;
+					let (tags, payloads) = (self.tags, self.payloads);
+					Some(unsafe { #ptrs_ref })
+				}
+			}
+			#[inline]
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let len = self.end - self.start;
+				(len, Some(len))
+			}
+		}
+		impl<#lt_a, #(#generic_params,)*> ::core::iter::DoubleEndedIterator for #iter_ident<#lt_a #(, #generic_args)*> #where_clause {
+			#[inline]
+			fn next_back(&mut self) -> Option<#ref_ident<#lt_a #(, #generic_args)*>> {
+				if self.start == self.end {
+					None
+				} else {
+					self.end -= 1;
+					let index = self.end;
+					let (tags, payloads) = (self.tags, self.payloads);
+					Some(unsafe { #ptrs_ref })
+				}
+			}
+		}
+		impl #impl_generics ::core::iter::ExactSizeIterator for #iter_ident<'_ #(, #generic_args)*> #where_clause {}
+		impl #impl_generics ::core::iter::FusedIterator for #iter_ident<'_ #(, #generic_args)*> #where_clause {}
+
+		impl<#lt_a, #(#generic_params,)*> ::core::iter::Iterator for #iter_mut_ident<#lt_a #(, #generic_args)*> #where_clause {
+			type Item = #mut_ident<#lt_a #(, #generic_args)*>;
+			#[inline]
+			fn next(&mut self) -> Option<#mut_ident<#lt_a #(, #generic_args)*>> {
+				if self.start == self.end {
+					None
+				} else {
+					let index = self.start;
+					self.start += 1;
+					let (tags, payloads) = (self.tags, self.payloads);
+					Some(unsafe { #ptrs_mut })
+				}
+			}
+			#[inline]
+			fn size_hint(&self) -> (usize, Option<usize>) {
+				let len = self.end - self.start;
+				(len, Some(len))
+			}
+		}
+		impl<#lt_a, #(#generic_params,)*> ::core::iter::DoubleEndedIterator for #iter_mut_ident<#lt_a #(, #generic_args)*> #where_clause {
+			#[inline]
+			fn next_back(&mut self) -> Option<#mut_ident<#lt_a #(, #generic_args)*>> {
+				if self.start == self.end {
+					None
+				} else {
+					self.end -= 1;
+					let index = self.end;
+					let (tags, payloads) = (self.tags, self.payloads);
+					Some(unsafe { #ptrs_mut })
+				}
+			}
+		}
+		impl #impl_generics ::core::iter::ExactSizeIterator for #iter_mut_ident<'_ #(, #generic_args)*> #where_clause {}
+		impl #impl_generics ::core::iter::FusedIterator for #iter_mut_ident<'_ #(, #generic_args)*> #where_clause {}
+
+		impl<#lt_a, #(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::IntoIterator for &#lt_a #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			type Item = #ref_ident<#lt_a #(, #generic_args)*>;
+			type IntoIter = #iter_ident<#lt_a #(, #generic_args)*>;
+			#[inline]
+			fn into_iter(self) -> #iter_ident<#lt_a #(, #generic_args)*> {
+				self.iter()
+			}
+		}
+		impl<#lt_a, #(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::iter::IntoIterator for &#lt_a mut #colvec_ident<#(#generic_args,)* #a> #where_clause {
+			type Item = #mut_ident<#lt_a #(, #generic_args)*>;
+			type IntoIter = #iter_mut_ident<#lt_a #(, #generic_args)*>;
+			#[inline]
+			fn into_iter(self) -> #iter_mut_ident<#lt_a #(, #generic_args)*> {
+				self.iter_mut()
+			}
+		}
+	};
+
+	let traits = quote! {
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator + ::core::clone::Clone> ::core::clone::Clone for #colvec_ident<#(#generic_args,)* #a>
+		where #(#where_preds,)*
+			#(#clone_bounds,)*
+		{
+			#[track_caller]
+			fn clone(&self) -> Self {
+				let mut colvec = Self::with_capacity_in(self.len, self.allocator().clone());
+				// The clone owns each row as soon as it is written, so a panicking clone
+				// drops the rows which were already cloned.
+				for (index, tag) in self.tags_slice().iter().enumerate() {
+					unsafe {
+						let payload = &*self.payloads_ptr().add(index);
+						let payload = match tag {
+							#(
+								#tag_ident::#variant_idents => {
+									let (#(#variant_bindings,)*) = &*payload.#variant_idents;
+									#payload_ident { #variant_idents: ::core::mem::ManuallyDrop::new((#(::core::clone::Clone::clone(#variant_bindings),)*)) }
+								},
+							)*
+						};
+						::core::ptr::write(colvec.tags_ptr().add(index), *tag);
+						::core::ptr::write(colvec.payloads_ptr().add(index), payload);
+					}
+					colvec.len = index + 1;
+				}
+				colvec
+			}
+		}
+
+		impl<#(#generic_params,)* #a: ::colvec::alloc::Allocator> ::core::fmt::Debug for #colvec_ident<#(#generic_args,)* #a>
+		where #(#where_preds,)*
+			#(#debug_bounds,)*
+		{
+			fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+				f.debug_list().entries(self.iter()).finish()
+			}
+		}
+	};
+
+	// names the generated items for code which is generic over the enum
+	let columnar = quote! {
+		impl #impl_generics ::colvec::Columnar for #enum_ty #where_clause {
+			const LAYOUT: ::core::alloc::Layout = <Self as ::colvec::fields::StructInfo<2>>::LAYOUT;
+			const FIELD_LAYOUTS: &'static [::core::alloc::Layout] = &<Self as ::colvec::fields::StructInfo<2>>::FIELDS.field_layouts();
+
+			type ColVec<#a: ::colvec::alloc::Allocator> = #colvec_ident<#(#generic_args,)* #a>;
+			type Ref<#lt_a> = #ref_ident<#lt_a #(, #generic_args)*> where Self: #lt_a;
+			type Mut<#lt_a> = #mut_ident<#lt_a #(, #generic_args)*> where Self: #lt_a;
+			// the payloads are only reachable through the rows
+			type Columns<#lt_a> = &#lt_a [#tag_ident] where Self: #lt_a;
+
+			#[inline]
+			fn new_in<#a: ::colvec::alloc::Allocator>(alloc: #a) -> Self::ColVec<#a> {
+				#colvec_ident::new_in(alloc)
+			}
+			#[inline]
+			#[track_caller]
+			fn with_capacity_in<#a: ::colvec::alloc::Allocator>(capacity: usize, alloc: #a) -> Self::ColVec<#a> {
+				#colvec_ident::with_capacity_in(capacity, alloc)
+			}
+			#[inline]
+			fn len<#a: ::colvec::alloc::Allocator>(colvec: &Self::ColVec<#a>) -> usize {
+				colvec.len()
+			}
+			#[inline]
+			fn capacity<#a: ::colvec::alloc::Allocator>(colvec: &Self::ColVec<#a>) -> usize {
+				colvec.capacity()
+			}
+			#[inline]
+			fn allocator<#a: ::colvec::alloc::Allocator>(colvec: &Self::ColVec<#a>) -> &#a {
+				colvec.allocator()
+			}
+			#[inline]
+			#[track_caller]
+			fn reserve<#a: ::colvec::alloc::Allocator>(colvec: &mut Self::ColVec<#a>, additional: usize) {
+				colvec.reserve(additional)
+			}
+			#[inline]
+			fn push<#a: ::colvec::alloc::Allocator>(colvec: &mut Self::ColVec<#a>, value: Self) {
+				colvec.push(value)
+			}
+			#[inline]
+			fn pop<#a: ::colvec::alloc::Allocator>(colvec: &mut Self::ColVec<#a>) -> Option<Self> {
+				colvec.pop()
+			}
+			#[inline]
+			fn truncate<#a: ::colvec::alloc::Allocator>(colvec: &mut Self::ColVec<#a>, len: usize) {
+				colvec.truncate(len)
+			}
+			#[inline]
+			fn get<#lt_a, #a: ::colvec::alloc::Allocator>(colvec: &#lt_a Self::ColVec<#a>, index: usize) -> Option<Self::Ref<#lt_a>> where Self: #lt_a {
+				colvec.get(index)
+			}
+			#[inline]
+			fn get_mut<#lt_a, #a: ::colvec::alloc::Allocator>(colvec: &#lt_a mut Self::ColVec<#a>, index: usize) -> Option<Self::Mut<#lt_a>> where Self: #lt_a {
+				colvec.get_mut(index)
+			}
+			#[inline]
+			fn columns<#lt_a, #a: ::colvec::alloc::Allocator>(colvec: &#lt_a Self::ColVec<#a>) -> Self::Columns<#lt_a> where Self: #lt_a {
+				colvec.tags_slice()
+			}
+		}
+	};
+
+	#[cfg_attr(not(feature = "std"), expect(unused_mut))]
+	let mut output=quote! {
+		#colvec
+
+		#columns
+
+		#struct_info
+
+		#impls
+
+		#iter
+
+		#traits
+
+		#columnar
+	};
+
+	#[cfg(feature = "std")]
+	output.extend(global);
+
+	output.into()
+}
+
+// Defaults are dropped, since the allocator parameter which follows them may not have one.
+fn strip_defaults(generics:&mut syn::Generics){
+	for param in &mut generics.params{
+		match param{
+			syn::GenericParam::Type(type_param)=>{
+				type_param.eq_token=None;
+				type_param.default=None;
+			},
+			syn::GenericParam::Const(const_param)=>{
+				const_param.eq_token=None;
+				const_param.default=None;
+			},
+			syn::GenericParam::Lifetime(_)=>(),
+		}
+	}
+}
+
+// The generic parameters as arguments, to name the input type or a generated type.
+fn generic_args(generics:&syn::Generics)->Vec<syn::GenericArgument>{
+	generics.params.iter().map(|param|->syn::GenericArgument{match param{
+		syn::GenericParam::Lifetime(lifetime_param)=>syn::GenericArgument::Lifetime(lifetime_param.lifetime.clone()),
+		syn::GenericParam::Type(type_param)=>{let ident=&type_param.ident;syn::parse_quote!{#ident}},
+		syn::GenericParam::Const(const_param)=>{let ident=&const_param.ident;syn::parse_quote!{#ident}},
+	}}).collect()
+}
+
+// Give the allocator type parameter, which is always last, a default of Global.
+#[cfg(feature = "std")]
+fn default_global(generics:&mut syn::Generics){
+	match generics.params.last_mut(){
+		Some(syn::GenericParam::Type(type_param))=>{
+			type_param.eq_token=Some(syn::Token![=](type_param.ident.span()));
+			type_param.default=Some(syn::parse_quote!{::colvec::alloc::Global});
+		},
+		// generated expression always ends with the allocator type param
+		_ => unreachable!(),
+	}
+}
+
+// Prefix a generic parameter name with underscores until the struct's generics don't use it.
+fn fresh_ident(generics:&syn::Generics,name:&str)->syn::Ident{
+	let mut name=name.to_owned();
+	while generics.params.iter().any(|param|match param{
+		syn::GenericParam::Type(type_param)=>type_param.ident==name,
+		syn::GenericParam::Const(const_param)=>const_param.ident==name,
+		syn::GenericParam::Lifetime(_)=>false,
+	}){
+		name.insert(0,'_');
+	}
+	quote::format_ident!("{name}")
+}
+fn fresh_lifetime(generics:&syn::Generics,name:&str)->syn::Lifetime{
+	let mut name=name.to_owned();
+	while generics.lifetimes().any(|lifetime_param|lifetime_param.lifetime.ident==name){
+		name.insert(0,'_');
+	}
+	let ident=quote::format_ident!("{name}");
+	syn::Lifetime{apostrophe:ident.span(),ident}
+}
+
+// A column's accessors are named after its field, or `col{index}` for a tuple field,
+// and have the field's visibility, unless overridden with `#[colvec(name = "...", vis = "...")]`.
+fn column_name_vis(index:usize,field:&syn::Field)->syn::Result<(syn::Ident,syn::Visibility)>{
+	let attributes=parse_attributes(&field.attrs,true)?;
+	let name=attributes.name.or_else(||field.ident.clone()).unwrap_or_else(||quote::format_ident!("col{index}"));
+	let vis=attributes.vis.unwrap_or_else(||field.vis.clone());
+	Ok((name,vis))
+}
+
+// A variant's accessors are named after the variant in snake case,
+// unless a name is given with `#[colvec(name = "...")]`.
+fn variant_name(variant:&syn::Variant)->syn::Result<syn::Ident>{
+	let name=parse_attributes(&variant.attrs,false)?.name;
+	Ok(name.unwrap_or_else(||{
+		let variant_string=variant.ident.to_string();
+		let variant_string=variant_string.strip_prefix("r#").unwrap_or(&variant_string);
+		let chars=variant_string.chars().collect::<Vec<_>>();
+		let mut name=String::new();
+		for (index,&c) in chars.iter().enumerate(){
+			if c.is_uppercase(){
+				// a word starts at an uppercase letter after a lowercase one,
+				// or at the last uppercase letter of an acronym, as in `HttpRequest` or `HTTPRequest`
+				let previous=index.checked_sub(1).map(|index|chars[index]);
+				let next=chars.get(index+1);
+				let starts_word=match previous{
+					Some(previous)=>previous.is_lowercase()||previous.is_ascii_digit()
+						||previous.is_uppercase()&&next.is_some_and(|next|next.is_lowercase()),
+					None=>false,
+				};
+				if starts_word{
+					name.push('_');
+				}
+				name.extend(c.to_lowercase());
+			}else{
+				name.push(c);
+			}
+		}
+		syn::Ident::new(&name,variant.ident.span())
+	}))
+}
+
+// How the columns of a struct are allocated, chosen with `#[colvec(storage = "...")]` on the struct.
+#[derive(Clone,Copy,PartialEq)]
+enum Storage{
+	// every column in one allocation, which is the default
+	Contiguous,
+	// an allocation for each column
+	PerColumn,
+}
+fn parse_storage(attrs:&[syn::Attribute])->syn::Result<Storage>{
+	let mut storage=None;
+	for attr in attrs{
+		if attr.path().is_ident("colvec"){
+			attr.parse_nested_meta(|meta|{
+				if meta.path.is_ident("storage"){
+					if storage.is_some(){
+						return Err(meta.error("duplicate colvec storage"));
+					}
+					let storage_lit:syn::LitStr=meta.value()?.parse()?;
+					storage=Some(match storage_lit.value().as_str(){
+						"contiguous"=>Storage::Contiguous,
+						"per_column"=>Storage::PerColumn,
+						_=>return Err(syn::Error::new(storage_lit.span(),"colvec storage should be \"contiguous\" or \"per_column\"")),
+					});
+					Ok(())
+				}else{
+					Err(meta.error("unsupported colvec attribute, expected `storage = \"...\"`"))
+				}
+			})?;
+		}
+	}
+	Ok(storage.unwrap_or(Storage::Contiguous))
+}
+
+// The options given with `#[colvec(...)]`.
+struct Attributes{
+	name:Option<syn::Ident>,
+	vis:Option<syn::Visibility>,
+}
+fn parse_attributes(attrs:&[syn::Attribute],allow_vis:bool)->syn::Result<Attributes>{
+	let mut attributes=Attributes{name:None,vis:None};
+	for attr in attrs{
+		if attr.path().is_ident("colvec"){
+			attr.parse_nested_meta(|meta|{
+				if meta.path.is_ident("name"){
+					if attributes.name.is_some(){
+						return Err(meta.error("duplicate colvec name"));
+					}
+					let name_lit:syn::LitStr=meta.value()?.parse()?;
+					let ident=name_lit.parse().map_err(|_|syn::Error::new(name_lit.span(),"colvec name should be an identifier"))?;
+					attributes.name=Some(ident);
+					Ok(())
+				}else if allow_vis&&meta.path.is_ident("vis"){
+					if attributes.vis.is_some(){
+						return Err(meta.error("duplicate colvec vis"));
+					}
+					// an empty string makes the column private
+					let vis_lit:syn::LitStr=meta.value()?.parse()?;
+					let vis=vis_lit.parse().map_err(|_|syn::Error::new(vis_lit.span(),"colvec vis should be a visibility, such as \"pub(crate)\""))?;
+					attributes.vis=Some(vis);
+					Ok(())
+				}else if allow_vis{
+					Err(meta.error("unsupported colvec attribute, expected `name = \"...\"` or `vis = \"...\"`"))
+				}else{
+					Err(meta.error("unsupported colvec attribute, expected `name = \"...\"`"))
+				}
+			})?;
+		}
+	}
+	Ok(attributes)
+}
+
+// Reject colvec attributes where none are supported.
+fn reject_attributes(attrs:&[syn::Attribute])->syn::Result<()>{
+	match attrs.iter().find(|attr|attr.path().is_ident("colvec")){
+		Some(attr)=>Err(syn::Error::new_spanned(attr,"colvec attributes are not supported here")),
+		None=>Ok(()),
+	}
+}
+
+// Bounds on the concrete field types would be rejected as trivially false, so they are
+// wrapped in a higher-ranked binder which defers the check until the item is used.
+fn field_bounds(field_types:&[syn::Type],bound:syn::Path)->Vec<syn::WherePredicate>{
+	field_types.iter().map(|ty|syn::parse_quote!{for<'__colvec> #ty: #bound}).collect()
 }
 
 #[cfg(test)]
@@ -0,0 +1,10 @@
+use colvec::ColVec;
+
+#[derive(ColVec)]
+struct Point {
+	#[colvec(name = "px", name = "x")]
+	x: f32,
+	y: f32,
+}
+
+fn main() {}
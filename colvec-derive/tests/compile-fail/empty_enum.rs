@@ -0,0 +1,6 @@
+use colvec::ColVec;
+
+#[derive(ColVec)]
+enum Never {}
+
+fn main() {}
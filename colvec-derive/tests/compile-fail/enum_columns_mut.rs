@@ -0,0 +1,17 @@
+use colvec::{ColVec, Columnar};
+
+#[derive(ColVec)]
+enum Shape {
+	Circle(f32),
+	Square(f32),
+}
+
+fn borrow_columns<T: Columnar>(cols: &mut colvec::ColVec<T>) {
+	cols.columns_mut();
+}
+
+fn main() {
+	let mut shapes = colvec::ColVec::<Shape>::new();
+	shapes.columns_mut();
+	borrow_columns(&mut shapes);
+}
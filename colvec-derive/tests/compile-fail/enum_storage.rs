@@ -0,0 +1,10 @@
+use colvec::ColVec;
+
+#[derive(ColVec)]
+#[colvec(storage = "per_column")]
+enum Shape {
+	Circle(f32),
+	Square(f32),
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use colvec::ColVec;
+
+#[derive(ColVec)]
+struct Point(#[colvec(name = "not an identifier")] f32, f32);
+
+fn main() {}
@@ -0,0 +1,10 @@
+use colvec::ColVec;
+
+#[derive(ColVec)]
+#[colvec(storage = "scattered")]
+struct Point {
+	x: f32,
+	y: f32,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use colvec::ColVec;
+
+#[derive(ColVec)]
+struct Point {
+	#[colvec(vis = "public")]
+	x: f32,
+	y: f32,
+}
+
+fn main() {}
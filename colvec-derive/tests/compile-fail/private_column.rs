@@ -0,0 +1,20 @@
+mod bank {
+	use colvec::ColVec;
+
+	#[derive(ColVec)]
+	pub struct Account {
+		pub id: u32,
+		balance: u64,
+	}
+
+	pub fn open(id: u32) -> Account {
+		Account { id, balance: 0 }
+	}
+}
+
+fn main() {
+	let mut accounts = bank::AccountColVec::new();
+	accounts.push(bank::open(0));
+	assert_eq!(&[0], accounts.id_slice());
+	accounts.balance_slice_mut()[0] = 1_000_000;
+}
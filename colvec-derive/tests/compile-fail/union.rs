@@ -0,0 +1,9 @@
+use colvec::ColVec;
+
+#[derive(ColVec)]
+union Bits {
+	int: u32,
+	float: f32,
+}
+
+fn main() {}
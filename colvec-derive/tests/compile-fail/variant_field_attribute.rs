@@ -0,0 +1,12 @@
+use colvec::ColVec;
+
+#[derive(ColVec)]
+enum Shape {
+	Circle {
+		#[colvec(name = "r")]
+		radius: f32,
+	},
+	Square(f32),
+}
+
+fn main() {}
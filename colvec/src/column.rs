@@ -0,0 +1,111 @@
+// Guards for the columns of generated types, which need no allocator.
+
+/// Drops the initialized elements of one column when it goes out of scope.
+///
+/// The generated `Drop` impls hold one of these per column so that a panicking
+/// destructor in one column does not leak the elements of the other columns.
+pub struct DropColumn<T> {
+	ptr: *mut T,
+	len: usize,
+}
+
+impl<T> DropColumn<T> {
+	/// # Safety
+	///
+	/// `ptr` must point to `len` initialized values of `T` which are not used again.
+	#[inline]
+	pub const unsafe fn new(ptr: *mut T, len: usize) -> Self {
+		Self { ptr, len }
+	}
+}
+
+impl<T> Drop for DropColumn<T> {
+	#[inline]
+	fn drop(&mut self) {
+		// SAFETY: Precondition passed to the caller of `new`
+		unsafe { core::ptr::drop_in_place(core::ptr::slice_from_raw_parts_mut(self.ptr, self.len)) }
+	}
+}
+
+/// Drops the payloads of a tag column and a payload column when it goes out of scope.
+///
+/// The payload column of an enum is a union, so each payload is dropped by `drop_payload`
+/// according to its tag. If one payload panics while dropping, the rest are still dropped
+/// during unwinding.
+pub struct DropPayloads<T, P> {
+	tags: *const T,
+	payloads: *mut P,
+	len: usize,
+	drop_payload: unsafe fn(&T, *mut P),
+}
+
+impl<T, P> DropPayloads<T, P> {
+	/// # Safety
+	///
+	/// `tags` and `payloads` must point to `len` initialized rows which are not used again,
+	/// and `drop_payload` must be safe to call with each tag and its payload.
+	#[inline]
+	pub const unsafe fn new(tags: *const T, payloads: *mut P, len: usize, drop_payload: unsafe fn(&T, *mut P)) -> Self {
+		Self { tags, payloads, len, drop_payload }
+	}
+}
+
+impl<T, P> Drop for DropPayloads<T, P> {
+	fn drop(&mut self) {
+		if self.len == 0 {
+			return;
+		}
+		// if a payload panics, `rest` is dropped while unwinding and carries on from the next row
+		let mut rest = Self { ..*self };
+		while rest.len != 0 {
+			let (tag, payload) = (rest.tags, rest.payloads);
+			// SAFETY: Precondition passed to the caller of `new`
+			unsafe {
+				rest.tags = tag.add(1);
+				rest.payloads = payload.add(1);
+				rest.len -= 1;
+				(rest.drop_payload)(&*tag, payload);
+			}
+		}
+	}
+}
+
+/// Clones `len` values from `src` into `dst`, returning a guard which owns the clones.
+///
+/// If a clone panics, the values which were already cloned into `dst` are dropped.
+///
+/// # Safety
+///
+/// `src` must point to `len` initialized values of `T`, and `dst` must be valid
+/// for writes of `len` values of `T`.
+#[inline]
+pub unsafe fn clone_column<T: Clone>(src: *const T, dst: *mut T, len: usize) -> DropColumn<T> {
+	// SAFETY: no values are owned yet
+	let mut column = unsafe { DropColumn::new(dst, 0) };
+	while column.len < len {
+		// SAFETY: Precondition passed to the caller
+		unsafe { dst.add(column.len).write((*src.add(column.len)).clone()) };
+		column.len += 1;
+	}
+	column
+}
+
+/// Clones the values at `indices` from `src` into `dst` in order, returning a guard which owns the clones.
+///
+/// If a clone panics, the values which were already cloned into `dst` are dropped.
+///
+/// # Safety
+///
+/// Every index must point to an initialized value of `T` in `src`, and `dst` must be valid
+/// for writes of `indices.len()` values of `T`.
+#[inline]
+pub unsafe fn gather_column<T: Clone>(src: *const T, dst: *mut T, indices: &[usize]) -> DropColumn<T> {
+	// SAFETY: no values are owned yet
+	let mut column = unsafe { DropColumn::new(dst, 0) };
+	for &index in indices {
+		// SAFETY: Precondition passed to the caller
+		unsafe { dst.add(column.len).write((*src.add(index)).clone()) };
+		column.len += 1;
+	}
+	column
+}
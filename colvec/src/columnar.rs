@@ -0,0 +1,233 @@
+use core::alloc::Layout;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+use crate::alloc::Allocator;
+#[cfg(feature = "std")]
+use crate::alloc::Global;
+
+/// A struct or enum which can be stored column by column, implemented by `#[derive(ColVec)]`.
+///
+/// The associated types name the items generated for the type, so that code can be
+/// written over a [`ColVec`] of any `T: Columnar`. The functions are the plumbing which
+/// [`ColVec`] forwards to, and the constants describe the columns.
+pub trait Columnar: Sized {
+	/// The layout of one row across every column, the same as [`StructInfo::LAYOUT`](crate::raw::StructInfo::LAYOUT).
+	const LAYOUT: Layout;
+	/// The layout of each column in declaration order, the tag and payload columns for an enum.
+	const FIELD_LAYOUTS: &'static [Layout];
+
+	/// The generated column vec, such as `ExampleColVec<A>`.
+	type ColVec<A: Allocator>;
+	/// The generated borrowed row, such as `ExampleRef<'a>`.
+	type Ref<'a> where Self: 'a;
+	/// The generated mutably borrowed row, such as `ExampleMut<'a>`.
+	type Mut<'a> where Self: 'a;
+	/// The generated struct of column slices, such as `ExampleColumns<'a>`, or the tags of an enum.
+	type Columns<'a> where Self: 'a;
+
+	fn new_in<A: Allocator>(alloc: A) -> Self::ColVec<A>;
+	fn with_capacity_in<A: Allocator>(capacity: usize, alloc: A) -> Self::ColVec<A>;
+	fn len<A: Allocator>(colvec: &Self::ColVec<A>) -> usize;
+	fn capacity<A: Allocator>(colvec: &Self::ColVec<A>) -> usize;
+	fn allocator<A: Allocator>(colvec: &Self::ColVec<A>) -> &A;
+	fn reserve<A: Allocator>(colvec: &mut Self::ColVec<A>, additional: usize);
+	fn push<A: Allocator>(colvec: &mut Self::ColVec<A>, value: Self);
+	fn pop<A: Allocator>(colvec: &mut Self::ColVec<A>) -> Option<Self>;
+	fn truncate<A: Allocator>(colvec: &mut Self::ColVec<A>, len: usize);
+	fn get<'a, A: Allocator>(colvec: &'a Self::ColVec<A>, index: usize) -> Option<Self::Ref<'a>> where Self: 'a;
+	fn get_mut<'a, A: Allocator>(colvec: &'a mut Self::ColVec<A>, index: usize) -> Option<Self::Mut<'a>> where Self: 'a;
+	fn columns<'a, A: Allocator>(colvec: &'a Self::ColVec<A>) -> Self::Columns<'a> where Self: 'a;
+}
+
+/// A [`Columnar`] struct whose columns can be borrowed mutably all at once.
+///
+/// Enums do not implement this, since changing a tag would reinterpret its payload.
+pub trait ColumnarMut: Columnar {
+	/// The generated struct of mutable column slices, such as `ExampleColumnsMut<'a>`.
+	type ColumnsMut<'a> where Self: 'a;
+
+	fn columns_mut<'a, A: Allocator>(colvec: &'a mut Self::ColVec<A>) -> Self::ColumnsMut<'a> where Self: 'a;
+}
+
+/// A column vec of any [`Columnar`] struct.
+///
+/// This wraps the generated `ExampleColVec<A>` and dereferences to it, so the
+/// struct-specific methods such as the column slices remain available.
+#[cfg(feature = "std")]
+pub struct ColVec<T: Columnar, A: Allocator = Global> {
+	inner: T::ColVec<A>,
+}
+/// A column vec of any [`Columnar`] struct.
+///
+/// This wraps the generated `ExampleColVec<A>` and dereferences to it, so the
+/// struct-specific methods such as the column slices remain available.
+#[cfg(not(feature = "std"))]
+pub struct ColVec<T: Columnar, A: Allocator> {
+	inner: T::ColVec<A>,
+}
+
+#[cfg(feature = "std")]
+impl<T: Columnar> ColVec<T, Global> {
+	#[inline]
+	#[must_use]
+	pub fn new() -> Self {
+		Self::new_in(Global)
+	}
+	#[inline]
+	#[must_use]
+	#[track_caller]
+	pub fn with_capacity(capacity: usize) -> Self {
+		Self::with_capacity_in(capacity, Global)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: Columnar> Default for ColVec<T, Global> {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Columnar, A: Allocator> ColVec<T, A> {
+	#[inline]
+	pub fn new_in(alloc: A) -> Self {
+		Self { inner: T::new_in(alloc) }
+	}
+	#[inline]
+	#[track_caller]
+	pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+		Self { inner: T::with_capacity_in(capacity, alloc) }
+	}
+	/// Wraps a generated column vec.
+	#[inline]
+	pub const fn from_inner(inner: T::ColVec<A>) -> Self {
+		Self { inner }
+	}
+	/// Unwraps the generated column vec.
+	#[inline]
+	pub fn into_inner(self) -> T::ColVec<A> {
+		self.inner
+	}
+	#[inline]
+	pub fn len(&self) -> usize {
+		T::len(&self.inner)
+	}
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+	#[inline]
+	pub fn capacity(&self) -> usize {
+		T::capacity(&self.inner)
+	}
+	#[inline]
+	pub fn allocator(&self) -> &A {
+		T::allocator(&self.inner)
+	}
+	#[track_caller]
+	pub fn reserve(&mut self, additional: usize) {
+		T::reserve(&mut self.inner, additional)
+	}
+	#[inline]
+	pub fn push(&mut self, value: T) {
+		T::push(&mut self.inner, value)
+	}
+	#[inline]
+	pub fn pop(&mut self) -> Option<T> {
+		T::pop(&mut self.inner)
+	}
+	#[inline]
+	pub fn truncate(&mut self, len: usize) {
+		T::truncate(&mut self.inner, len)
+	}
+	#[inline]
+	pub fn clear(&mut self) {
+		self.truncate(0)
+	}
+	#[inline]
+	pub fn get(&self, index: usize) -> Option<T::Ref<'_>> {
+		T::get(&self.inner, index)
+	}
+	#[inline]
+	pub fn get_mut(&mut self, index: usize) -> Option<T::Mut<'_>> {
+		T::get_mut(&mut self.inner, index)
+	}
+	#[inline]
+	pub fn columns(&self) -> T::Columns<'_> {
+		T::columns(&self.inner)
+	}
+}
+
+impl<T: ColumnarMut, A: Allocator> ColVec<T, A> {
+	#[inline]
+	pub fn columns_mut(&mut self) -> T::ColumnsMut<'_> {
+		T::columns_mut(&mut self.inner)
+	}
+}
+
+impl<T: Columnar, A: Allocator> Deref for ColVec<T, A> {
+	type Target = T::ColVec<A>;
+	#[inline]
+	fn deref(&self) -> &Self::Target {
+		&self.inner
+	}
+}
+impl<T: Columnar, A: Allocator> DerefMut for ColVec<T, A> {
+	#[inline]
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.inner
+	}
+}
+
+impl<T: Columnar, A: Allocator> Extend<T> for ColVec<T, A> {
+	#[track_caller]
+	fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+		let iter = iter.into_iter();
+		let (lower, _) = iter.size_hint();
+		self.reserve(lower);
+		for value in iter {
+			self.push(value);
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: Columnar> FromIterator<T> for ColVec<T, Global> {
+	#[track_caller]
+	fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+		let mut colvec = Self::new();
+		colvec.extend(iter);
+		colvec
+	}
+}
+
+impl<T: Columnar, A: Allocator> Clone for ColVec<T, A>
+where
+	T::ColVec<A>: Clone,
+{
+	#[inline]
+	fn clone(&self) -> Self {
+		Self { inner: self.inner.clone() }
+	}
+}
+
+impl<T: Columnar, A: Allocator> fmt::Debug for ColVec<T, A>
+where
+	T::ColVec<A>: fmt::Debug,
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.inner.fmt(f)
+	}
+}
+
+impl<T: Columnar, A: Allocator> PartialEq for ColVec<T, A>
+where
+	T::ColVec<A>: PartialEq,
+{
+	#[inline]
+	fn eq(&self, other: &Self) -> bool {
+		self.inner == other.inner
+	}
+}
@@ -0,0 +1,472 @@
+use core::alloc::Layout;
+use core::any::{TypeId, type_name};
+use core::mem::MaybeUninit;
+use core::num::NonZero;
+use core::ptr;
+
+use crate::alloc::Allocator;
+#[cfg(feature = "std")]
+use crate::alloc::Global;
+use crate::fields::{DynFields, copy_columns};
+use crate::raw::RawColVecInner;
+use crate::vec::Vec;
+
+/// The layout of one column of a [`DynColVec`], and how its values are dropped.
+#[derive(Clone, Copy, Debug)]
+pub struct DynColumn {
+	layout: Layout,
+	type_id: Option<TypeId>,
+	drop: Option<unsafe fn(*mut u8)>,
+}
+
+impl DynColumn {
+	/// A column of `T`, which can be accessed as `&[T]` and drops its values as `T`.
+	pub fn of<T: 'static>() -> Self {
+		unsafe fn drop_value<T>(value: *mut u8) {
+			// SAFETY: the column only holds values of T
+			unsafe { ptr::drop_in_place(value.cast::<T>()) }
+		}
+		Self {
+			layout: Layout::new::<T>(),
+			type_id: Some(TypeId::of::<T>()),
+			drop: if core::mem::needs_drop::<T>() { Some(drop_value::<T>) } else { None },
+		}
+	}
+	/// An untyped column of values with `layout`, which are not dropped.
+	///
+	/// The size of the layout is padded to a multiple of its alignment.
+	pub const fn from_layout(layout: Layout) -> Self {
+		Self { layout: layout.pad_to_align(), type_id: None, drop: None }
+	}
+	/// An untyped column of values with `layout`, which are dropped by `drop`.
+	///
+	/// The size of the layout is padded to a multiple of its alignment.
+	///
+	/// # Safety
+	///
+	/// `drop` must be safe to call once with a pointer to each value pushed into the column.
+	pub const unsafe fn from_layout_with_drop(layout: Layout, drop: unsafe fn(*mut u8)) -> Self {
+		Self { layout: layout.pad_to_align(), type_id: None, drop: Some(drop) }
+	}
+	#[inline]
+	pub const fn layout(&self) -> Layout {
+		self.layout
+	}
+	/// The type of the values, if the column was made by [`DynColumn::of`].
+	#[inline]
+	pub const fn type_id(&self) -> Option<TypeId> {
+		self.type_id
+	}
+}
+
+// Frees the allocation on drop, like RawColVec, but with a layout known at runtime.
+struct RawDynColVec<A: Allocator> {
+	inner: RawColVecInner<A>,
+	layout: Layout,
+	fields: DynFields,
+}
+
+impl<A: Allocator> Drop for RawDynColVec<A> {
+	fn drop(&mut self) {
+		// SAFETY: We are in a Drop impl, self.inner will not be used again.
+		unsafe { self.inner.deallocate(self.layout) }
+	}
+}
+
+/// A column vec whose columns are only known at runtime, such as an archetype table.
+///
+/// The columns share one allocation and are laid out like the columns of a generated
+/// `ExampleColVec`. Rows are pushed one typed value per column with [`DynColVec::push_row`]
+/// or through raw pointers, and each column can be borrowed as bytes or, if it was made by
+/// [`DynColumn::of`], as a typed slice.
+///
+/// The values of the columns are not known to be `Send` or `Sync`, so a DynColVec is only
+/// `Send` and `Sync` when `SEND_SYNC` is set by one of the unsafe `*_send_sync` constructors.
+#[cfg(feature = "std")]
+pub struct DynColVec<A: Allocator = Global, const SEND_SYNC: bool = false> {
+	buf: RawDynColVec<A>,
+	len: usize,
+	columns: Vec<DynColumn>,
+}
+/// A column vec whose columns are only known at runtime, such as an archetype table.
+///
+/// The columns share one allocation and are laid out like the columns of a generated
+/// `ExampleColVec`. Rows are pushed one typed value per column with [`DynColVec::push_row`]
+/// or through raw pointers, and each column can be borrowed as bytes or, if it was made by
+/// [`DynColumn::of`], as a typed slice.
+///
+/// The values of the columns are not known to be `Send` or `Sync`, so a DynColVec is only
+/// `Send` and `Sync` when `SEND_SYNC` is set by one of the unsafe `*_send_sync` constructors.
+#[cfg(not(feature = "std"))]
+pub struct DynColVec<A: Allocator, const SEND_SYNC: bool = false> {
+	buf: RawDynColVec<A>,
+	len: usize,
+	columns: Vec<DynColumn>,
+}
+// SAFETY: the caller of the constructor promised that every column's values are Send and Sync
+unsafe impl<A: Allocator + Send> Send for DynColVec<A, true> {}
+unsafe impl<A: Allocator + Sync> Sync for DynColVec<A, true> {}
+
+#[cfg(feature = "std")]
+impl DynColVec<Global> {
+	#[inline]
+	#[must_use]
+	pub fn new(columns: impl IntoIterator<Item = DynColumn>) -> Self {
+		Self::new_in(columns, Global)
+	}
+	#[inline]
+	#[must_use]
+	#[track_caller]
+	pub fn with_capacity(columns: impl IntoIterator<Item = DynColumn>, capacity: usize) -> Self {
+		Self::with_capacity_in(columns, capacity, Global)
+	}
+}
+
+#[cfg(feature = "std")]
+impl DynColVec<Global, true> {
+	/// # Safety
+	///
+	/// The values of every column must be `Send` and `Sync`.
+	#[inline]
+	#[must_use]
+	pub unsafe fn new_send_sync(columns: impl IntoIterator<Item = DynColumn>) -> Self {
+		unsafe { Self::new_send_sync_in(columns, Global) }
+	}
+	/// # Safety
+	///
+	/// The values of every column must be `Send` and `Sync`.
+	#[inline]
+	#[must_use]
+	#[track_caller]
+	pub unsafe fn with_capacity_send_sync(columns: impl IntoIterator<Item = DynColumn>, capacity: usize) -> Self {
+		unsafe { Self::with_capacity_send_sync_in(columns, capacity, Global) }
+	}
+}
+
+impl<A: Allocator> DynColVec<A> {
+	#[inline]
+	#[track_caller]
+	pub fn new_in(columns: impl IntoIterator<Item = DynColumn>, alloc: A) -> Self {
+		Self::from_columns(columns, |layout| RawColVecInner::new_in(alloc, NonZero::new(layout.align()).unwrap()))
+	}
+	#[inline]
+	#[track_caller]
+	pub fn with_capacity_in(columns: impl IntoIterator<Item = DynColumn>, capacity: usize, alloc: A) -> Self {
+		Self::from_columns(columns, |layout| RawColVecInner::with_capacity_in(capacity, alloc, layout))
+	}
+}
+
+impl<A: Allocator> DynColVec<A, true> {
+	/// # Safety
+	///
+	/// The values of every column must be `Send` and `Sync`.
+	#[inline]
+	#[track_caller]
+	pub unsafe fn new_send_sync_in(columns: impl IntoIterator<Item = DynColumn>, alloc: A) -> Self {
+		Self::from_columns(columns, |layout| RawColVecInner::new_in(alloc, NonZero::new(layout.align()).unwrap()))
+	}
+	/// # Safety
+	///
+	/// The values of every column must be `Send` and `Sync`.
+	#[inline]
+	#[track_caller]
+	pub unsafe fn with_capacity_send_sync_in(columns: impl IntoIterator<Item = DynColumn>, capacity: usize, alloc: A) -> Self {
+		Self::from_columns(columns, |layout| RawColVecInner::with_capacity_in(capacity, alloc, layout))
+	}
+}
+
+impl<A: Allocator, const SEND_SYNC: bool> DynColVec<A, SEND_SYNC> {
+	#[track_caller]
+	fn from_columns(columns: impl IntoIterator<Item = DynColumn>, make_inner: impl FnOnce(Layout) -> RawColVecInner<A>) -> Self {
+		let columns = columns.into_iter().collect::<Vec<_>>();
+		let sizes = columns.iter().map(|column| column.layout.size()).collect::<Vec<_>>();
+		let fields = DynFields::from_sizes(&sizes);
+		let align = columns.iter().map(|column| column.layout.align()).max().unwrap_or(1);
+		let Ok(layout) = Layout::from_size_align(fields.size(), align) else {
+			panic!("row size overflows isize");
+		};
+		Self {
+			buf: RawDynColVec { inner: make_inner(layout), layout, fields },
+			len: 0,
+			columns,
+		}
+	}
+	/// The columns, in the order they were given.
+	#[inline]
+	pub fn columns(&self) -> &[DynColumn] {
+		&self.columns
+	}
+	#[inline]
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+	#[inline]
+	pub const fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+	#[inline]
+	pub const fn capacity(&self) -> usize {
+		self.buf.inner.capacity(self.buf.layout.size())
+	}
+	#[inline]
+	pub fn allocator(&self) -> &A {
+		self.buf.inner.allocator()
+	}
+	#[track_caller]
+	pub fn reserve(&mut self, additional: usize) {
+		self.buf.inner.reserve(self.len, additional, self.buf.layout, self.buf.fields.sorted_fields());
+	}
+	#[inline]
+	fn column_ptr(&self, index: usize) -> *mut u8 {
+		// SAFETY: every column starts inside of (or one past the end of) the allocation
+		unsafe { self.buf.inner.ptr.as_ptr().add(self.capacity() * self.buf.fields.offset_of(index)) }
+	}
+	/// The bytes of the column at `index`.
+	///
+	/// # Safety
+	///
+	/// Every byte of the column's values must be initialized, so their type must not contain
+	/// padding. [`DynColVec::column_uninit_bytes`] has no such requirement.
+	///
+	/// # Panics
+	///
+	/// Panics if `index` is out of bounds of the columns.
+	#[inline]
+	pub unsafe fn column_bytes(&self, index: usize) -> &[u8] {
+		let size = self.columns[index].layout.size();
+		unsafe { core::slice::from_raw_parts(self.column_ptr(index), self.len * size) }
+	}
+	/// The bytes of the column at `index`.
+	///
+	/// # Safety
+	///
+	/// Every byte of the column's values must be initialized, so their type must not contain
+	/// padding, and every value must still be valid for the column's type after the bytes are written.
+	///
+	/// # Panics
+	///
+	/// Panics if `index` is out of bounds of the columns.
+	#[inline]
+	pub unsafe fn column_bytes_mut(&mut self, index: usize) -> &mut [u8] {
+		let size = self.columns[index].layout.size();
+		unsafe { core::slice::from_raw_parts_mut(self.column_ptr(index), self.len * size) }
+	}
+	/// The bytes of the column at `index`, which are `MaybeUninit` since the values may contain padding.
+	///
+	/// # Panics
+	///
+	/// Panics if `index` is out of bounds of the columns.
+	#[inline]
+	pub fn column_uninit_bytes(&self, index: usize) -> &[MaybeUninit<u8>] {
+		let size = self.columns[index].layout.size();
+		unsafe { core::slice::from_raw_parts(self.column_ptr(index).cast(), self.len * size) }
+	}
+	/// The column at `index` as a slice of `T`, if it exists and was made by `DynColumn::of::<T>()`.
+	#[inline]
+	pub fn column<T: 'static>(&self, index: usize) -> Option<&[T]> {
+		let column = self.columns.get(index)?;
+		if column.type_id != Some(TypeId::of::<T>()) {
+			return None;
+		}
+		Some(unsafe { core::slice::from_raw_parts(self.column_ptr(index).cast(), self.len) })
+	}
+	/// The column at `index` as a mutable slice of `T`, if it exists and was made by `DynColumn::of::<T>()`.
+	#[inline]
+	pub fn column_mut<T: 'static>(&mut self, index: usize) -> Option<&mut [T]> {
+		let column = self.columns.get(index)?;
+		if column.type_id != Some(TypeId::of::<T>()) {
+			return None;
+		}
+		Some(unsafe { core::slice::from_raw_parts_mut(self.column_ptr(index).cast(), self.len) })
+	}
+	/// Appends a row given one typed value per column, in order, see [`DynRow`].
+	#[track_caller]
+	pub fn push_row(&mut self) -> DynRow<'_, A, SEND_SYNC> {
+		if self.len == self.capacity() {
+			self.buf.inner.grow_one(self.buf.layout, self.buf.fields.sorted_fields());
+		}
+		DynRow { colvec: self, column: 0 }
+	}
+	/// Appends a row by moving one value into each column from `values`.
+	///
+	/// # Safety
+	///
+	/// Each pointer must point to a valid value for its column, which is moved into the
+	/// column and must not be used or dropped again.
+	///
+	/// # Panics
+	///
+	/// Panics if there is not one value for each column.
+	#[track_caller]
+	pub unsafe fn push_raw(&mut self, values: &[*const u8]) {
+		assert_eq!(values.len(), self.columns.len(), "push_raw should be given one value per column");
+		let len = self.len;
+		if len == self.capacity() {
+			self.buf.inner.grow_one(self.buf.layout, self.buf.fields.sorted_fields());
+		}
+		for (index, (&value, column)) in values.iter().zip(&self.columns).enumerate() {
+			let size = column.layout.size();
+			unsafe { ptr::copy_nonoverlapping(value, self.column_ptr(index).add(len * size), size) };
+		}
+		self.len = len + 1;
+	}
+	/// Removes the row at `index` by moving each of its values out into `values`,
+	/// and moves the last row into its place.
+	///
+	/// # Safety
+	///
+	/// Each pointer must be valid for writing one value of its column.
+	///
+	/// # Panics
+	///
+	/// Panics if `index` is out of bounds, or if there is not one pointer for each column.
+	#[track_caller]
+	pub unsafe fn swap_remove_raw(&mut self, index: usize, values: &[*mut u8]) {
+		let len = self.len;
+		assert!(index < len, "swap_remove_raw index (is {index}) should be < len (is {len})");
+		assert_eq!(values.len(), self.columns.len(), "swap_remove_raw should be given one pointer per column");
+		for (column_index, (&value, column)) in values.iter().zip(&self.columns).enumerate() {
+			let size = column.layout.size();
+			unsafe { ptr::copy_nonoverlapping(self.column_ptr(column_index).add(index * size), value, size) };
+		}
+		unsafe { copy_columns(self.buf.fields.sorted_fields(), self.buf.inner.ptr.as_ptr(), self.capacity(), len - 1, index, 1) };
+		self.len = len - 1;
+	}
+	/// Removes the row at `index` and drops its values, and moves the last row into its place.
+	///
+	/// # Panics
+	///
+	/// Panics if `index` is out of bounds.
+	#[track_caller]
+	pub fn swap_remove(&mut self, index: usize) {
+		let len = self.len;
+		assert!(index < len, "swap_remove index (is {index}) should be < len (is {len})");
+		// the row is swapped to the end and dropped there, after the length no longer includes it
+		if index != len - 1 {
+			for (column_index, column) in self.columns.iter().enumerate() {
+				let size = column.layout.size();
+				let column_ptr = self.column_ptr(column_index);
+				unsafe { ptr::swap_nonoverlapping(column_ptr.add(index * size), column_ptr.add((len - 1) * size), size) };
+			}
+		}
+		self.len = len - 1;
+		unsafe { self.drop_rows(len - 1, len) };
+	}
+	pub fn truncate(&mut self, len: usize) {
+		if len > self.len {
+			return;
+		}
+		let old_len = self.len;
+		// Shrink the length first, in case a value panics while dropping.
+		self.len = len;
+		unsafe { self.drop_rows(len, old_len) };
+	}
+	#[inline]
+	pub fn clear(&mut self) {
+		self.truncate(0);
+	}
+	/// Drops the rows in `start..end` of every column.
+	///
+	/// # Safety
+	///
+	/// The rows must be initialized and must not be used again.
+	unsafe fn drop_rows(&mut self, start: usize, end: usize) {
+		// If a value panics while dropping, the guard is dropped while unwinding
+		// and carries on from the next value.
+		struct DropRows<'a, A: Allocator, const SEND_SYNC: bool> {
+			colvec: &'a DynColVec<A, SEND_SYNC>,
+			column: usize,
+			row: usize,
+			start: usize,
+			end: usize,
+		}
+		impl<A: Allocator, const SEND_SYNC: bool> Drop for DropRows<'_, A, SEND_SYNC> {
+			fn drop(&mut self) {
+				if self.column == self.colvec.columns.len() {
+					return;
+				}
+				let mut rest = DropRows { ..*self };
+				while rest.column < rest.colvec.columns.len() {
+					let column = &rest.colvec.columns[rest.column];
+					match column.drop {
+						Some(drop) if rest.row < rest.end => {
+							// SAFETY: the row is initialized and is dropped only once
+							unsafe {
+								let value = rest.colvec.column_ptr(rest.column).add(rest.row * column.layout.size());
+								rest.row += 1;
+								drop(value);
+							}
+						},
+						_ => {
+							rest.column += 1;
+							rest.row = rest.start;
+						},
+					}
+				}
+			}
+		}
+
+		let _rows = DropRows { colvec: self, column: 0, row: start, start, end };
+	}
+}
+
+impl<A: Allocator, const SEND_SYNC: bool> Drop for DynColVec<A, SEND_SYNC> {
+	fn drop(&mut self) {
+		// RawDynColVec handles deallocation afterwards.
+		unsafe { self.drop_rows(0, self.len) }
+	}
+}
+
+/// A row being appended to a [`DynColVec`] by [`DynColVec::push_row`].
+///
+/// The row is only appended by [`DynRow::finish`], once every column has a value.
+/// If it is dropped before then, the values it was given are dropped.
+pub struct DynRow<'a, A: Allocator, const SEND_SYNC: bool = false> {
+	colvec: &'a mut DynColVec<A, SEND_SYNC>,
+	column: usize,
+}
+
+impl<A: Allocator, const SEND_SYNC: bool> DynRow<'_, A, SEND_SYNC> {
+	/// Moves `value` into the next column.
+	///
+	/// # Panics
+	///
+	/// Panics if every column already has a value, or if the next column was not made by `DynColumn::of::<T>()`.
+	#[track_caller]
+	pub fn with<T: 'static>(mut self, value: T) -> Self {
+		let column = self.column;
+		let Some(dyn_column) = self.colvec.columns.get(column) else {
+			panic!("the row already has a value for each of its {column} columns");
+		};
+		assert!(dyn_column.type_id == Some(TypeId::of::<T>()), "column {column} does not hold values of {}", type_name::<T>());
+		// SAFETY: push_row made room for one more row, and the column holds values of T
+		unsafe { ptr::write(self.colvec.column_ptr(column).cast::<T>().add(self.colvec.len), value) };
+		self.column = column + 1;
+		self
+	}
+	/// Appends the row.
+	///
+	/// # Panics
+	///
+	/// Panics if a column has no value.
+	#[track_caller]
+	pub fn finish(self) {
+		let columns = self.colvec.columns.len();
+		assert!(self.column == columns, "the row has values for {} of its {columns} columns", self.column);
+		self.colvec.len += 1;
+		// the values belong to the DynColVec now
+		core::mem::forget(self);
+	}
+}
+
+impl<A: Allocator, const SEND_SYNC: bool> Drop for DynRow<'_, A, SEND_SYNC> {
+	fn drop(&mut self) {
+		let row = self.colvec.len;
+		for (index, column) in self.colvec.columns[..self.column].iter().enumerate() {
+			if let Some(drop) = column.drop {
+				// SAFETY: the value was written by DynRow::with and is not part of the DynColVec yet
+				unsafe { drop(self.colvec.column_ptr(index).add(row * column.layout.size())) };
+			}
+		}
+	}
+}
@@ -1,4 +1,5 @@
 use core::alloc::Layout;
+use core::fmt;
 
 /// The error type for `try_reserve` methods.
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -15,6 +16,7 @@ impl TryReserveError {
 	}
 }
 
+/// Details of the allocation that caused a `TryReserveError`
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum TryReserveErrorKind {
 	/// Error due to the computed capacity exceeding the collection's maximum
@@ -34,3 +36,20 @@ impl From<TryReserveErrorKind> for TryReserveError {
 		Self { kind }
 	}
 }
+
+impl fmt::Display for TryReserveError {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt.write_str("memory allocation failed")?;
+		let reason = match self.kind {
+			TryReserveErrorKind::CapacityOverflow => {
+				" because the computed capacity exceeded the collection's maximum"
+			}
+			TryReserveErrorKind::AllocError { .. } => {
+				" because the memory allocator returned an error"
+			}
+		};
+		fmt.write_str(reason)
+	}
+}
+
+impl core::error::Error for TryReserveError {}
@@ -1,16 +1,29 @@
+use core::alloc::Layout;
+
+#[cfg(feature = "alloc")]
+use crate::vec::Vec;
+
+// TODO: don't do this
+pub trait StructInfo<const N:usize> {
+	const LAYOUT:Layout;
+	const FIELDS:Fields::<N>;
+}
+
 pub struct Fields<const N:usize>{
+	field_layouts:[Layout;N],
 	field_id_to_offset:[usize;N],
 	sorted_fields:[Field;N],
 }
 
+// The fields are sorted by descending offset, so the last field is at offset 0.
 #[derive(Clone,Copy)]
-struct Field{
+pub(crate) struct Field{
 	size:usize,
 	offset:usize,
 }
 
 impl<const N:usize> Fields<N>{
-	pub const fn from_sizes(sizes:[usize;N])->Self{
+	pub const fn from_layouts(field_layouts:[Layout;N])->Self{
 		// encode a u64 with size in the upper bits and index in the lower bits
 		let mut index=0;
 		let mut sides_encoded=[0u64;N];
@@ -18,8 +31,8 @@ impl<const N:usize> Fields<N>{
 		assert!(N<u32::MAX as usize);
 		while index<N{
 			// don't have 4GB structs or my code won't work
-			assert!(sizes[index]<u32::MAX as usize);
-			let size=sizes[index] as u64;
+			assert!(field_layouts[index].size()<u32::MAX as usize);
+			let size=field_layouts[index].size() as u64;
 			sides_encoded[index]=(size<<32)|(index as u64);
 			index+=1;
 		}
@@ -44,10 +57,27 @@ impl<const N:usize> Fields<N>{
 		}
 
 		Fields{
+			field_layouts,
 			field_id_to_offset,
 			sorted_fields,
 		}
 	}
+	/// The same as `from_layouts`, for code generated before the fields had layouts.
+	///
+	/// Each field is taken to be byte-aligned, which the contiguous storage does not
+	/// rely on since its alignment comes from `StructInfo::LAYOUT`.
+	pub const fn from_sizes(sizes:[usize;N])->Self{
+		let mut field_layouts=[Layout::new::<()>();N];
+		let mut index=0;
+		while index<N{
+			field_layouts[index]=match Layout::from_size_align(sizes[index],1){
+				Ok(layout)=>layout,
+				Err(_)=>panic!("field size overflows isize"),
+			};
+			index+=1;
+		}
+		Self::from_layouts(field_layouts)
+	}
 	pub const fn size(&self)->usize{
 		let mut size=0;
 		let mut i=0;
@@ -60,6 +90,50 @@ impl<const N:usize> Fields<N>{
 	pub const fn offset_of(&self,index:usize)->usize{
 		self.field_id_to_offset[index]
 	}
+	pub const fn layout_of(&self,index:usize)->Layout{
+		self.field_layouts[index]
+	}
+	pub const fn field_layouts(&self)->[Layout;N]{
+		self.field_layouts
+	}
+	/// The offset of the column for the field at `index` in an inline buffer of `capacity` rows.
+	///
+	/// The columns are in the same order as in a heap buffer, but `capacity` need not be a
+	/// multiple of the row's alignment, so each column is moved up to its field's alignment.
+	/// That takes less than `self.size()` more bytes. Zero-sized columns start at 0.
+	pub const fn inline_offset_of(&self,index:usize,capacity:usize)->usize{
+		if self.field_layouts[index].size()==0{
+			return 0;
+		}
+		let mut end=0usize;
+		// walk the columns by ascending offset, the zero-sized ones come last
+		let mut i=N;
+		while 0<i{
+			i-=1;
+			let field=self.sorted_fields[i];
+			let start=end.next_multiple_of(self.align_at(field.offset));
+			if field.offset==self.field_id_to_offset[index]{
+				return start;
+			}
+			end=start+capacity*field.size;
+		}
+		unreachable!()
+	}
+	// the alignment of the sized field at offset
+	const fn align_at(&self,offset:usize)->usize{
+		let mut index=0;
+		while index<N{
+			if self.field_layouts[index].size()!=0&&self.field_id_to_offset[index]==offset{
+				return self.field_layouts[index].align();
+			}
+			index+=1;
+		}
+		unreachable!()
+	}
+	#[cfg(feature = "alloc")]
+	pub(crate) const fn sorted_fields(&self)->&[Field]{
+		&self.sorted_fields
+	}
 	// SAFETY:
 	// ptr must be aligned
 	// old_capacity must be a multiple of <T as StructInfo>::LAYOUT.align()
@@ -73,17 +147,22 @@ impl<const N:usize> Fields<N>{
 		new_capacity: usize,
 		len: usize,
 	){
-		// the fields are moved in descending-offset order, and the field at 0 offset is skipped
-		let mut i=0;
-		while i<N-1{
-			unsafe {
-				let src = ptr.add(old_capacity * self.sorted_fields[i].offset);
-				let dst = ptr.add(new_capacity * self.sorted_fields[i].offset);
-				let count = len * self.sorted_fields[i].size;
-				core::ptr::copy_nonoverlapping(src, dst, count);
-			}
-			i+=1;
-		}
+		unsafe { grow_columns(&self.sorted_fields, ptr, old_capacity, new_capacity, len) }
+	}
+	// SAFETY:
+	// ptr must be aligned
+	// old_capacity must be a multiple of <T as StructInfo>::LAYOUT.align()
+	// new_capacity must be a multiple of <T as StructInfo>::LAYOUT.align()
+	// len must not exceed new_capacity
+	// new_capacity must not exceed old_capacity
+	pub const unsafe fn shrink_fields(
+		&self,
+		ptr: *mut u8,
+		old_capacity: usize,
+		new_capacity: usize,
+		len: usize,
+	){
+		unsafe { shrink_columns(&self.sorted_fields, ptr, old_capacity, new_capacity, len) }
 	}
 	// SAFETY:
 	// src must be aligned
@@ -101,18 +180,398 @@ impl<const N:usize> Fields<N>{
 		dst_start_index: usize,
 		count: usize,
 	){
-		debug_assert!(dst_start_index+count<=dst_capacity);
-		// the fields are moved in any order, field 0 is not skipped
+		unsafe { move_columns(&self.sorted_fields, src, dst, src_capacity, dst_capacity, dst_start_index, count) }
+	}
+	// SAFETY:
+	// ptr must be aligned
+	// capacity must be a multiple of <T as StructInfo>::LAYOUT.align()
+	// src_index + count must not exceed capacity
+	// dst_index + count must not exceed capacity
+	pub const unsafe fn copy_fields(
+		&self,
+		ptr: *mut u8,
+		capacity: usize,
+		src_index: usize,
+		dst_index: usize,
+		count: usize,
+	){
+		unsafe { copy_columns(&self.sorted_fields, ptr, capacity, src_index, dst_index, count) }
+	}
+	// SAFETY:
+	// ptr must be aligned
+	// capacity must be a multiple of <T as StructInfo>::LAYOUT.align()
+	// start <= mid <= end
+	// end must not exceed capacity
+	pub const unsafe fn rotate_fields(
+		&self,
+		ptr: *mut u8,
+		capacity: usize,
+		start: usize,
+		mid: usize,
+		end: usize,
+	){
+		debug_assert!(start<=mid&&mid<=end);
+		debug_assert!(end<=capacity);
 		let mut i=0;
 		while i<N{
 			unsafe {
-				let src = src.add(src_capacity * self.sorted_fields[i].offset);
-				let dst = dst.add(dst_capacity * self.sorted_fields[i].offset);
-				let dst = dst.add(dst_start_index * self.sorted_fields[i].size);
-				let count = count * self.sorted_fields[i].size;
-				core::ptr::copy_nonoverlapping(src, dst, count);
+				let column = ptr.add(capacity * self.sorted_fields[i].offset);
+				rotate_column(column, self.sorted_fields[i].size, start, mid, end);
 			}
 			i+=1;
 		}
 	}
+	// SAFETY:
+	// ptr must be aligned
+	// capacity must be a multiple of <T as StructInfo>::LAYOUT.align()
+	// len must not exceed capacity
+	// the first len rows must be initialized
+	// keep and drop_row must only access the row at the index they are passed
+	pub unsafe fn retain(
+		&self,
+		ptr: *mut u8,
+		capacity: usize,
+		len: &mut usize,
+		keep: impl FnMut(usize) -> bool,
+		drop_row: impl FnMut(usize),
+	){
+		unsafe {
+			retain_rows(len, keep, drop_row, |src_index, dst_index, count| self.copy_fields(ptr, capacity, src_index, dst_index, count))
+		}
+	}
+	// SAFETY:
+	// ptr must be aligned
+	// capacity must be a multiple of <T as StructInfo>::LAYOUT.align()
+	// permutation.len() must not exceed capacity
+	// the first permutation.len() rows must be initialized
+	// permutation must contain every index in 0..permutation.len() exactly once
+	pub unsafe fn apply_permutation(
+		&self,
+		ptr: *mut u8,
+		capacity: usize,
+		permutation: &mut [usize],
+	){
+		let columns=self.sorted_fields.iter().map(|field|(unsafe{ ptr.add(capacity*field.offset) },field.size));
+		unsafe { permute_columns(columns, permutation) }
+	}
+}
+
+/// The runtime counterpart of [`Fields`], for columns which are only known at runtime.
+#[cfg(feature = "alloc")]
+pub(crate) struct DynFields{
+	size:usize,
+	field_id_to_offset:Vec<usize>,
+	sorted_fields:Vec<Field>,
+}
+
+#[cfg(feature = "alloc")]
+impl DynFields{
+	pub(crate) fn from_sizes(sizes:&[usize])->Self{
+		// the same order as Fields::from_layouts: by size, and index as a tie breaker
+		let mut sorted_indices=(0..sizes.len()).collect::<Vec<_>>();
+		sorted_indices.sort_unstable_by_key(|&index|(sizes[index],index));
+		let mut field_id_to_offset=core::iter::repeat_n(0,sizes.len()).collect::<Vec<_>>();
+		let mut sorted_fields=sorted_indices.iter().map(|&index|Field{
+			size:sizes[index],
+			offset:0,
+		}).collect::<Vec<_>>();
+		// saturate so that an overflowing row is rejected when its Layout is made
+		let mut offset=0usize;
+		for (field,&index) in sorted_fields.iter_mut().zip(&sorted_indices).rev(){
+			field_id_to_offset[index]=offset;
+			field.offset=offset;
+			offset=offset.saturating_add(field.size);
+		}
+		DynFields{
+			size:offset,
+			field_id_to_offset,
+			sorted_fields,
+		}
+	}
+	pub(crate) fn size(&self)->usize{
+		self.size
+	}
+	pub(crate) fn offset_of(&self,index:usize)->usize{
+		self.field_id_to_offset[index]
+	}
+	pub(crate) fn sorted_fields(&self)->&[Field]{
+		&self.sorted_fields
+	}
+}
+
+// The column moves below are shared by Fields and DynFields.
+// Their safety requirements are those of the Fields methods of the same name.
+
+pub(crate) const unsafe fn grow_columns(
+	sorted_fields: &[Field],
+	ptr: *mut u8,
+	old_capacity: usize,
+	new_capacity: usize,
+	len: usize,
+){
+	// the fields are moved in descending-offset order, and the field at 0 offset is skipped
+	// a column can overlap with its own old location when the capacity grows by less than len,
+	// but never with the location of another column
+	let mut i=0;
+	while i<sorted_fields.len().saturating_sub(1){
+		unsafe {
+			let src = ptr.add(old_capacity * sorted_fields[i].offset);
+			let dst = ptr.add(new_capacity * sorted_fields[i].offset);
+			let count = len * sorted_fields[i].size;
+			core::ptr::copy(src, dst, count);
+		}
+		i+=1;
+	}
+}
+
+pub(crate) const unsafe fn shrink_columns(
+	sorted_fields: &[Field],
+	ptr: *mut u8,
+	old_capacity: usize,
+	new_capacity: usize,
+	len: usize,
+){
+	// the reverse of grow_fields: the fields are moved in ascending-offset order,
+	// so that every column lands on memory which has already been vacated
+	let mut i=sorted_fields.len().saturating_sub(1);
+	while 0<i{
+		i-=1;
+		unsafe {
+			let src = ptr.add(old_capacity * sorted_fields[i].offset);
+			let dst = ptr.add(new_capacity * sorted_fields[i].offset);
+			let count = len * sorted_fields[i].size;
+			core::ptr::copy(src, dst, count);
+		}
+	}
+}
+
+pub(crate) const unsafe fn move_columns(
+	sorted_fields: &[Field],
+	src: *const u8,
+	dst: *mut u8,
+	src_capacity: usize,
+	dst_capacity: usize,
+	dst_start_index: usize,
+	count: usize,
+){
+	debug_assert!(dst_start_index+count<=dst_capacity);
+	// the fields are moved in any order, field 0 is not skipped
+	let mut i=0;
+	while i<sorted_fields.len(){
+		unsafe {
+			let src = src.add(src_capacity * sorted_fields[i].offset);
+			let dst = dst.add(dst_capacity * sorted_fields[i].offset);
+			let dst = dst.add(dst_start_index * sorted_fields[i].size);
+			let count = count * sorted_fields[i].size;
+			core::ptr::copy_nonoverlapping(src, dst, count);
+		}
+		i+=1;
+	}
+}
+
+pub(crate) const unsafe fn copy_columns(
+	sorted_fields: &[Field],
+	ptr: *mut u8,
+	capacity: usize,
+	src_index: usize,
+	dst_index: usize,
+	count: usize,
+){
+	debug_assert!(src_index+count<=capacity);
+	debug_assert!(dst_index+count<=capacity);
+	// the fields are copied within their own column, so the source and destination may overlap
+	let mut i=0;
+	while i<sorted_fields.len(){
+		unsafe {
+			let column = ptr.add(capacity * sorted_fields[i].offset);
+			let src = column.add(src_index * sorted_fields[i].size);
+			let dst = column.add(dst_index * sorted_fields[i].size);
+			let count = count * sorted_fields[i].size;
+			core::ptr::copy(src, dst, count);
+		}
+		i+=1;
+	}
+}
+
+// The row shuffles below are shared by Fields and RawColumns, which pass the columns
+// as (pointer, element size) or as a closure which copies rows within every column.
+
+// SAFETY:
+// len must not exceed the capacity of the columns
+// the first len rows must be initialized
+// keep and drop_row must only access the row at the index they are passed
+// copy_rows(src_index, dst_index, count) must copy count rows within every column
+pub(crate) unsafe fn retain_rows(
+	len: &mut usize,
+	mut keep: impl FnMut(usize) -> bool,
+	mut drop_row: impl FnMut(usize),
+	copy_rows: impl FnMut(usize, usize, usize),
+){
+	let original_len = *len;
+
+	if original_len == 0 {
+		// Empty case: explicit return allows better optimization, vs letting compiler infer it
+		return;
+	}
+
+	// Avoid double drop if the drop guard is not executed,
+	// since we may make some holes during the process.
+	*len = 0;
+
+	// Vec: [Kept, Kept, Hole, Hole, Hole, Hole, Unchecked, Unchecked]
+	//      |<-              processed len   ->| ^- next to check
+	//                  |<-  deleted cnt     ->|
+	//      |<-              original_len                          ->|
+	// Kept: Rows which predicate returns true on.
+	// Hole: Moved or dropped row slot.
+	// Unchecked: Unchecked valid rows.
+	//
+	// This drop guard will be invoked when predicate or `drop` of row panicked.
+	// It shifts unchecked rows to cover holes and sets `len` to the correct length.
+	// In cases when predicate and `drop` never panick, it will be optimized out.
+	struct BackshiftOnDrop<'a, C: FnMut(usize, usize, usize)> {
+		copy_rows: C,
+		len: &'a mut usize,
+		processed_len: usize,
+		deleted_cnt: usize,
+		original_len: usize,
+	}
+
+	impl<C: FnMut(usize, usize, usize)> Drop for BackshiftOnDrop<'_, C> {
+		fn drop(&mut self) {
+			if self.deleted_cnt > 0 {
+				// Trailing unchecked rows must be valid since we never touch them.
+				(self.copy_rows)(
+					self.processed_len,
+					self.processed_len - self.deleted_cnt,
+					self.original_len - self.processed_len,
+				);
+			}
+			*self.len = self.original_len - self.deleted_cnt;
+		}
+	}
+
+	let mut g = BackshiftOnDrop { copy_rows, len, processed_len: 0, deleted_cnt: 0, original_len };
+
+	while g.processed_len != original_len {
+		let index = g.processed_len;
+		if !keep(index) {
+			// Advance early to avoid double drop if `drop_row` panicked.
+			g.processed_len += 1;
+			g.deleted_cnt += 1;
+			drop_row(index);
+			continue;
+		}
+		if g.deleted_cnt > 0 {
+			// `deleted_cnt` > 0, so the hole slot must not overlap with current row.
+			// We use copy for move, and never touch this row again.
+			(g.copy_rows)(index, index - g.deleted_cnt, 1);
+		}
+		g.processed_len += 1;
+	}
+
+	// All rows are processed. This can be optimized to `set_len` by LLVM.
+	drop(g);
+}
+
+// SAFETY:
+// every column must be valid for reads and writes of the first permutation.len() elements
+// permutation must contain every index in 0..permutation.len() exactly once
+pub(crate) unsafe fn permute_columns(
+	columns: impl IntoIterator<Item = (*mut u8, usize)>,
+	permutation: &mut [usize],
+){
+	// Each column is permuted on its own by following each cycle of the permutation
+	// and swapping the elements along it, which moves the element at permutation[i] to i.
+	// Visited indices are marked by flipping their bits, which is unambiguous because
+	// a valid index never has its top bit set. Rather than restoring the marks after
+	// every column, the next column treats flipped indices as the unvisited ones.
+	let len=permutation.len();
+	let mut flipped=false;
+	for (column,size) in columns{
+		if size==0{
+			continue;
+		}
+		for start in 0..len{
+			if (len<=permutation[start])!=flipped{
+				continue;
+			}
+			let mut i=start;
+			loop{
+				let next=if flipped{ !permutation[i] }else{ permutation[i] };
+				permutation[i]= !permutation[i];
+				if next==start{
+					break;
+				}
+				unsafe{
+					core::ptr::swap_nonoverlapping(column.add(i*size), column.add(next*size), size);
+				}
+				i=next;
+			}
+		}
+		flipped= !flipped;
+	}
+	if flipped{
+		for index in permutation{
+			*index= !*index;
+		}
+	}
+}
+
+// SAFETY:
+// column must be valid for reads and writes of the elements in start..end
+// start <= mid <= end
+pub(crate) const unsafe fn rotate_column(column: *mut u8, size: usize, start: usize, mid: usize, end: usize){
+	// rotate the column left so that the element at mid becomes the element at start,
+	// using three reversals so that no temporary storage is needed
+	unsafe {
+		reverse(column, size, start, mid);
+		reverse(column, size, mid, end);
+		reverse(column, size, start, end);
+	}
+}
+
+// SAFETY:
+// column must be valid for reads and writes of the elements in start..end
+const unsafe fn reverse(column: *mut u8, size: usize, start: usize, end: usize){
+	if end<=start{
+		return;
+	}
+	let mut a=start;
+	let mut b=end-1;
+	while a<b{
+		unsafe {
+			core::ptr::swap_nonoverlapping(column.add(a*size), column.add(b*size), size);
+		}
+		a+=1;
+		b-=1;
+	}
+}
+
+/// Checks that `permutation` contains every index in `0..permutation.len()` exactly once.
+///
+/// Seen indices are temporarily marked by flipping the bits of the entry they point to,
+/// and every entry is restored before returning.
+pub fn is_permutation(permutation: &mut [usize]) -> bool{
+	let len=permutation.len();
+	// a flipped index is never in bounds, so the marks can only be told apart from in bounds indices
+	if permutation.iter().any(|&index|len<=index){
+		return false;
+	}
+	let unmark=|index:usize|if len<=index{ !index }else{ index };
+	let mut marked=0;
+	while marked<len{
+		let index=unmark(permutation[marked]);
+		if len<=permutation[index]{
+			// index was already seen
+			break;
+		}
+		permutation[index]= !permutation[index];
+		marked+=1;
+	}
+	for i in 0..marked{
+		let index=unmark(permutation[i]);
+		permutation[index]= !permutation[index];
+	}
+	marked==len
 }
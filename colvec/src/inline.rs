@@ -0,0 +1,37 @@
+use core::mem::MaybeUninit;
+
+/// The inline storage of a generated `ExampleArrayColVec`, with room for the columns of
+/// `CAP` rows at the offsets given by [`Fields::inline_offset_of`](crate::fields::Fields::inline_offset_of).
+///
+/// `SIZE` is the size of the row `T`, and the buffer is aligned for it. Each row holds at least
+/// `LAYOUT.size()` bytes, since the columns leave out its padding, and one more row makes room
+/// for aligning each column. The rows are plain bytes, so moving the buffer keeps all of them.
+#[repr(C)]
+pub struct InlineBuffer<T, const SIZE: usize, const CAP: usize> {
+	_align: [T; 0],
+	rows: [[MaybeUninit<u8>; SIZE]; CAP],
+	slack: [MaybeUninit<u8>; SIZE],
+}
+
+impl<T, const SIZE: usize, const CAP: usize> InlineBuffer<T, SIZE, CAP> {
+	#[inline]
+	#[must_use]
+	pub const fn new() -> Self {
+		Self { _align: [], rows: [[MaybeUninit::uninit(); SIZE]; CAP], slack: [MaybeUninit::uninit(); SIZE] }
+	}
+	#[inline]
+	pub const fn as_ptr(&self) -> *const u8 {
+		(self as *const Self).cast()
+	}
+	#[inline]
+	pub const fn as_mut_ptr(&mut self) -> *mut u8 {
+		(self as *mut Self).cast()
+	}
+}
+
+impl<T, const SIZE: usize, const CAP: usize> Default for InlineBuffer<T, SIZE, CAP> {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
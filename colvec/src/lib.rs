@@ -3,18 +3,40 @@
 
 #[cfg(all(feature = "allocator-api2", feature = "nightly"))]
 compile_error!("feature \"allocator-api2\" and feature \"nightly\" cannot be enabled at the same time");
-#[cfg(all(not(feature = "allocator-api2"), not(feature = "nightly")))]
-compile_error!("one of feature \"allocator-api2\" and feature \"nightly\" must be enabled");
+#[cfg(all(feature = "alloc", not(feature = "allocator-api2"), not(feature = "nightly")))]
+compile_error!("one of feature \"allocator-api2\" and feature \"nightly\" must be enabled with feature \"alloc\"");
 
 pub use colvec_derive::ColVec;
 
 mod error;
+pub use error::{TryReserveError, TryReserveErrorKind};
+
+#[cfg(feature = "alloc")]
+mod columnar;
+#[cfg(feature = "alloc")]
+pub use columnar::{ColVec, Columnar, ColumnarMut};
+
+#[cfg(feature = "alloc")]
+mod dynamic;
+#[cfg(feature = "alloc")]
+pub use dynamic::{DynColVec, DynColumn, DynRow};
 
 // used from generated code
 
+#[cfg(feature = "alloc")]
 #[doc(hidden)]
 pub mod alloc;
 #[doc(hidden)]
+pub mod column;
+#[doc(hidden)]
 pub mod fields;
 #[doc(hidden)]
+pub mod inline;
+#[doc(hidden)]
+pub mod range;
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
 pub mod raw;
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub mod vec;
@@ -3,17 +3,36 @@
 
 fn main(){}
 
-// Idea: multi-slice like Vec<T> Derefs to &[T]
-// RawColVec Derefs to &RawColSlice
-// &RawColSlice has .field1_slice
-// RawColVec DerefMuts to &mut RawColSlice
-// &mut RawColSlice has .field1_slice_mut
-//
-// RawColSlice is a ZST but is !Sized to get the fat pointer
-
 #[cfg(test)]
 mod tests {
 	use ::colvec::*;
+	use core::sync::atomic::{AtomicUsize,Ordering};
+
+	extern crate std;
+
+	#[cfg(feature = "std")]
+	use ::colvec::alloc::Global;
+	#[cfg(not(feature = "std"))]
+	use global::Global;
+
+	/// Counts how many times it has been dropped.
+	#[derive(Clone)]
+	struct DropCounter(&'static AtomicUsize);
+	impl Drop for DropCounter {
+		fn drop(&mut self) {
+			self.0.fetch_add(1, Ordering::Relaxed);
+		}
+	}
+
+	/// Panics when dropped if the flag is set.
+	struct PanicOnDrop(bool);
+	impl Drop for PanicOnDrop {
+		fn drop(&mut self) {
+			if self.0 {
+				panic!("PanicOnDrop");
+			}
+		}
+	}
 
 	#[cfg(not(feature = "std"))]
 	mod global {
@@ -77,15 +96,15 @@ mod tests {
 	#[test]
 	fn test_zst(){
 		#[derive(ColVec)]
-		struct ZST{}
+		struct Zst{}
 
 		#[cfg(feature = "std")]
-		let mut test=ZSTColVec::new();
+		let mut test=ZstColVec::new();
 		#[cfg(not(feature = "std"))]
-		let mut test=ZSTColVec::new_in(global::Global);
+		let mut test=ZstColVec::new_in(global::Global);
 
-		test.push(ZST{});
-		test.push(ZST{});
+		test.push(Zst{});
+		test.push(Zst{});
 
 		assert_eq!(2, test.len());
 	}
@@ -121,4 +140,1612 @@ mod tests {
 		assert_eq!(&[false,true], bugs.is_red_slice());
 		assert_eq!(&[1,1337], bugs.coolness_slice());
 	}
+
+	#[test]
+	fn test_drop() {
+		#[derive(ColVec)]
+		struct Droppy{
+			small:DropCounter,
+			big:[DropCounter;3],
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+
+		{
+			#[cfg(feature = "std")]
+			let mut droppies=DroppyColVec::new();
+			#[cfg(not(feature = "std"))]
+			let mut droppies=DroppyColVec::new_in(global::Global);
+			for _ in 0..5 {
+				droppies.push(Droppy{
+					small:DropCounter(&COUNT),
+					big:[DropCounter(&COUNT),DropCounter(&COUNT),DropCounter(&COUNT)],
+				});
+			}
+			assert_eq!(0, COUNT.load(Ordering::Relaxed));
+		}
+
+		assert_eq!(5*4, COUNT.load(Ordering::Relaxed));
+	}
+
+	#[test]
+	fn test_drop_into_raw_parts() {
+		#[derive(ColVec)]
+		struct Droppy{
+			counter:DropCounter,
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+
+		#[cfg(feature = "std")]
+		let mut droppies=DroppyColVec::new();
+		#[cfg(not(feature = "std"))]
+		let mut droppies=DroppyColVec::new_in(global::Global);
+		droppies.push(Droppy{counter:DropCounter(&COUNT)});
+
+		let (ptr,len,capacity,alloc)=droppies.into_raw_parts_with_alloc();
+		assert_eq!(0, COUNT.load(Ordering::Relaxed));
+
+		let droppies=unsafe{DroppyColVec::from_raw_parts_in(ptr,len,capacity,alloc)};
+		drop(droppies);
+		assert_eq!(1, COUNT.load(Ordering::Relaxed));
+	}
+
+	#[test]
+	fn test_drop_panic() {
+		#[derive(ColVec)]
+		struct Droppy{
+			before:DropCounter,
+			panicky:PanicOnDrop,
+			after:DropCounter,
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+
+		let result=std::panic::catch_unwind(std::panic::AssertUnwindSafe(||{
+			#[cfg(feature = "std")]
+			let mut droppies=DroppyColVec::new();
+			#[cfg(not(feature = "std"))]
+			let mut droppies=DroppyColVec::new_in(global::Global);
+			droppies.push(Droppy{
+				before:DropCounter(&COUNT),
+				panicky:PanicOnDrop(false),
+				after:DropCounter(&COUNT),
+			});
+			droppies.push(Droppy{
+				before:DropCounter(&COUNT),
+				panicky:PanicOnDrop(true),
+				after:DropCounter(&COUNT),
+			});
+			droppies.push(Droppy{
+				before:DropCounter(&COUNT),
+				panicky:PanicOnDrop(false),
+				after:DropCounter(&COUNT),
+			});
+		}));
+
+		assert!(result.is_err());
+		// every column is still dropped when one of them panics
+		assert_eq!(6, COUNT.load(Ordering::Relaxed));
+	}
+
+	/// An allocator which moves every block it grows, like a realloc which cannot extend in place.
+	#[derive(Clone,Copy)]
+	struct Relocating;
+	unsafe impl alloc::Allocator for Relocating {
+		fn allocate(&self, layout: core::alloc::Layout) -> Result<core::ptr::NonNull<[u8]>, alloc::AllocError> {
+			alloc::Allocator::allocate(&Global, layout)
+		}
+		unsafe fn deallocate(&self, ptr: core::ptr::NonNull<u8>, layout: core::alloc::Layout) {
+			unsafe{alloc::Allocator::deallocate(&Global, ptr, layout)}
+		}
+	}
+
+	#[test]
+	fn test_grow_relocated() {
+		#[derive(ColVec)]
+		struct Bug{
+			is_red:bool,
+			coolness:u64,
+		}
+
+		let mut bugs=BugColVec::new_in(Relocating);
+		for coolness in 0..9 {
+			bugs.push(Bug{
+				is_red:coolness%2==0,
+				coolness,
+			});
+		}
+
+		assert_eq!(&[0,1,2,3,4,5,6,7,8], bugs.coolness_slice());
+		assert_eq!(&[true,false,true,false,true,false,true,false,true], bugs.is_red_slice());
+	}
+
+	#[derive(ColVec,Debug,PartialEq)]
+	struct Row{
+		id:u32,
+		flag:bool,
+		name:&'static str,
+	}
+	fn row(id:u32)->Row{
+		Row{id,flag:id.is_multiple_of(2),name:"row"}
+	}
+	fn rows(ids:impl IntoIterator<Item=u32>)->RowColVec<Global>{
+		let mut rows=RowColVec::new_in(Global);
+		for id in ids{
+			rows.push(row(id));
+		}
+		rows
+	}
+
+	#[test]
+	fn test_pop() {
+		let mut rows=rows(0..3);
+		assert_eq!(Some(Row{id:2,flag:true,name:"row"}), rows.pop());
+		assert_eq!(Some(Row{id:1,flag:false,name:"row"}), rows.pop());
+		assert_eq!(&[0], rows.id_slice());
+		assert_eq!(Some(Row{id:0,flag:true,name:"row"}), rows.pop());
+		assert_eq!(None, rows.pop());
+		assert_eq!(0, rows.len());
+	}
+
+	#[test]
+	fn test_swap_remove() {
+		let mut rows=rows(0..4);
+		assert_eq!(Row{id:1,flag:false,name:"row"}, rows.swap_remove(1));
+		assert_eq!(&[0,3,2], rows.id_slice());
+		assert_eq!(&[true,false,true], rows.flag_slice());
+		assert_eq!(Row{id:2,flag:true,name:"row"}, rows.swap_remove(2));
+		assert_eq!(&[0,3], rows.id_slice());
+	}
+
+	#[test]
+	#[should_panic(expected = "swap_remove index (is 2) should be < len (is 2)")]
+	fn test_swap_remove_out_of_bounds() {
+		rows(0..2).swap_remove(2);
+	}
+
+	#[test]
+	fn test_remove() {
+		let mut rows=rows(0..4);
+		assert_eq!(Row{id:1,flag:false,name:"row"}, rows.remove(1));
+		assert_eq!(&[0,2,3], rows.id_slice());
+		assert_eq!(&[true,true,false], rows.flag_slice());
+		assert_eq!(Row{id:3,flag:false,name:"row"}, rows.remove(2));
+		assert_eq!(Row{id:0,flag:true,name:"row"}, rows.remove(0));
+		assert_eq!(&[2], rows.id_slice());
+	}
+
+	#[test]
+	#[should_panic(expected = "removal index (is 0) should be < len (is 0)")]
+	fn test_remove_out_of_bounds() {
+		rows(0..0).remove(0);
+	}
+
+	#[test]
+	fn test_truncate_clear() {
+		#[derive(ColVec)]
+		struct Droppy{
+			id:u8,
+			counter:DropCounter,
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+
+		let mut droppies=DroppyColVec::new_in(Global);
+		for id in 0..5 {
+			droppies.push(Droppy{id,counter:DropCounter(&COUNT)});
+		}
+
+		droppies.truncate(10);
+		assert_eq!(0, COUNT.load(Ordering::Relaxed));
+		droppies.truncate(2);
+		assert_eq!(3, COUNT.load(Ordering::Relaxed));
+		assert_eq!(&[0,1], droppies.id_slice());
+		let capacity=droppies.capacity();
+		droppies.clear();
+		assert_eq!(5, COUNT.load(Ordering::Relaxed));
+		assert_eq!(0, droppies.len());
+		assert_eq!(capacity, droppies.capacity());
+		drop(droppies);
+		assert_eq!(5, COUNT.load(Ordering::Relaxed));
+	}
+
+	#[test]
+	fn test_insert() {
+		let mut rows=rows([1,3]);
+		rows.insert(0,row(0));
+		rows.insert(2,row(2));
+		rows.insert(4,row(4));
+		assert_eq!(&[0,1,2,3,4], rows.id_slice());
+		assert_eq!(&[true,false,true,false,true], rows.flag_slice());
+	}
+
+	#[test]
+	#[should_panic(expected = "insertion index (is 3) should be <= len (is 2)")]
+	fn test_insert_out_of_bounds() {
+		rows(0..2).insert(3,row(3));
+	}
+
+	#[test]
+	fn test_splice() {
+		// replacement is shorter than the range
+		let mut rows=rows(0..6);
+		let removed:std::vec::Vec<u32>=rows.splice(1..4,[row(10)]).map(|row|row.id).collect();
+		assert_eq!(&[1,2,3], removed.as_slice());
+		assert_eq!(&[0,10,4,5], rows.id_slice());
+		assert_eq!(&[true,true,true,false], rows.flag_slice());
+
+		// replacement is longer than the range
+		rows.splice(1..2,(20..24).map(row));
+		assert_eq!(&[0,20,21,22,23,4,5], rows.id_slice());
+		assert_eq!(&[true,true,false,true,false,true,false], rows.flag_slice());
+
+		// replacement is the same length as the range
+		rows.splice(..2,[row(30),row(31)]);
+		assert_eq!(&[30,31,21,22,23,4,5], rows.id_slice());
+
+		// insert at the end
+		rows.splice(7..,[row(40)]);
+		assert_eq!(&[30,31,21,22,23,4,5,40], rows.id_slice());
+	}
+
+	#[test]
+	fn test_splice_partially_consumed() {
+		#[derive(ColVec)]
+		struct Droppy{
+			id:u8,
+			counter:DropCounter,
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+		let droppy=|id|Droppy{id,counter:DropCounter(&COUNT)};
+
+		let mut droppies=DroppyColVec::new_in(Global);
+		for id in 0..6 {
+			droppies.push(droppy(id));
+		}
+
+		let mut splice=droppies.splice(1..5,[droppy(10),droppy(11),droppy(12)]);
+		assert_eq!(Some(1), splice.next().map(|droppy|droppy.id));
+		assert_eq!(Some(4), splice.next_back().map(|droppy|droppy.id));
+		assert_eq!(2, splice.len());
+		assert_eq!(2, COUNT.load(Ordering::Relaxed));
+		drop(splice);
+		assert_eq!(4, COUNT.load(Ordering::Relaxed));
+		assert_eq!(&[0,10,11,12,5], droppies.id_slice());
+
+		drop(droppies);
+		assert_eq!(9, COUNT.load(Ordering::Relaxed));
+	}
+
+	#[test]
+	fn test_extend_from_within() {
+		#[derive(ColVec)]
+		struct Named{
+			id:u32,
+			name:std::string::String,
+		}
+		let mut named=NamedColVec::new_in(Global);
+		for (id,name) in [(0,"zero"),(1,"one"),(2,"two")]{
+			named.push(Named{id,name:name.into()});
+		}
+
+		named.extend_from_within(1..);
+		named.extend_from_within(..=0);
+		assert_eq!(&[0,1,2,1,2,0], named.id_slice());
+		assert_eq!(&["zero","one","two","one","two","zero"], named.name_slice());
+	}
+
+	#[test]
+	#[should_panic(expected = "range end index 4 out of range for slice of length 3")]
+	fn test_extend_from_within_out_of_bounds() {
+		rows(0..3).extend_from_within(2..4);
+	}
+
+	#[test]
+	fn test_get() {
+		let mut rows=rows(0..3);
+
+		let row=rows.get(1).unwrap();
+		assert_eq!((&1,&false,&"row"), (row.id,row.flag,row.name));
+		assert!(rows.get(3).is_none());
+		assert_eq!(Some(&0), rows.first().map(|row|row.id));
+		assert_eq!(Some(&2), rows.last().map(|row|row.id));
+		assert_eq!(&2, unsafe{rows.get_unchecked(2)}.id);
+
+		let row=rows.get_mut(1).unwrap();
+		*row.id=10;
+		*row.flag=true;
+		*rows.first_mut().unwrap().name="first";
+		*rows.last_mut().unwrap().name="last";
+		assert!(rows.get_mut(3).is_none());
+		assert_eq!(&[0,10,2], rows.id_slice());
+		assert_eq!(&[true,true,true], rows.flag_slice());
+		assert_eq!(&["first","row","last"], rows.name_slice());
+
+		let empty=self::rows(0..0);
+		assert!(empty.first().is_none());
+		assert!(empty.last().is_none());
+	}
+
+	#[test]
+	fn test_iter() {
+		let mut rows=rows(0..4);
+
+		let mut iter=rows.iter();
+		assert_eq!(4, iter.len());
+		assert_eq!(Some(&0), iter.next().map(|row|row.id));
+		assert_eq!(Some(&3), iter.next_back().map(|row|row.id));
+		assert_eq!(2, iter.len());
+		let ids:std::vec::Vec<u32>=iter.map(|row|*row.id).collect();
+		assert_eq!(&[1,2], ids.as_slice());
+
+		for row in &mut rows{
+			*row.id*=10;
+			*row.flag^=true;
+		}
+		for row in rows.iter_mut().rev().take(1){
+			*row.name="last";
+		}
+		assert_eq!(&[0,10,20,30], rows.id_slice());
+		assert_eq!(&[false,true,false,true], rows.flag_slice());
+		assert_eq!(&["row","row","row","last"], rows.name_slice());
+
+		let names:std::vec::Vec<&str>=(&rows).into_iter().map(|row|*row.name).collect();
+		assert_eq!(&["row","row","row","last"], names.as_slice());
+
+		let mut iter=rows.iter_mut();
+		assert!(iter.nth(4).is_none());
+		assert!(iter.next().is_none());
+	}
+
+	#[test]
+	fn test_into_iter() {
+		let rows=rows(0..4);
+		let mut into_iter=rows.into_iter();
+		assert_eq!(Some(row(0)), into_iter.next());
+		assert_eq!(Some(row(3)), into_iter.next_back());
+		assert_eq!(2, into_iter.len());
+		let rows:std::vec::Vec<Row>=into_iter.collect();
+		assert_eq!(&[row(1),row(2)], rows.as_slice());
+	}
+
+	#[test]
+	fn test_into_iter_drop() {
+		#[derive(ColVec)]
+		struct Droppy{
+			id:u8,
+			counter:DropCounter,
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+
+		let mut droppies=DroppyColVec::new_in(Global);
+		for id in 0..5 {
+			droppies.push(Droppy{id,counter:DropCounter(&COUNT)});
+		}
+
+		let mut into_iter=droppies.into_iter();
+		assert_eq!(Some(0), into_iter.next().map(|droppy|droppy.id));
+		assert_eq!(Some(4), into_iter.next_back().map(|droppy|droppy.id));
+		assert_eq!(2, COUNT.load(Ordering::Relaxed));
+		drop(into_iter);
+		assert_eq!(5, COUNT.load(Ordering::Relaxed));
+	}
+
+	#[test]
+	fn test_drain() {
+		let mut rows=rows(0..6);
+		let drained:std::vec::Vec<Row>=rows.drain(1..3).collect();
+		assert_eq!(&[row(1),row(2)], drained.as_slice());
+		assert_eq!(&[0,3,4,5], rows.id_slice());
+		assert_eq!(&[true,false,true,false], rows.flag_slice());
+
+		let mut drain=rows.drain(1..);
+		assert_eq!(Some(row(5)), drain.next_back());
+		drop(drain);
+		assert_eq!(&[0], rows.id_slice());
+
+		rows.drain(..);
+		assert_eq!(0, rows.len());
+	}
+
+	#[test]
+	fn test_drain_drop() {
+		#[derive(ColVec)]
+		struct Droppy{
+			id:u8,
+			counter:DropCounter,
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+
+		let mut droppies=DroppyColVec::new_in(Global);
+		for id in 0..6 {
+			droppies.push(Droppy{id,counter:DropCounter(&COUNT)});
+		}
+
+		let mut drain=droppies.drain(1..5);
+		assert_eq!(Some(1), drain.next().map(|droppy|droppy.id));
+		drop(drain);
+		assert_eq!(4, COUNT.load(Ordering::Relaxed));
+		assert_eq!(&[0,5], droppies.id_slice());
+
+		// leaking the Drain leaks the tail instead of exposing moved rows
+		core::mem::forget(droppies.drain(..1));
+		assert_eq!(0, droppies.len());
+	}
+
+	#[test]
+	fn test_retain() {
+		let mut rows=rows(0..8);
+		rows.retain(|row|*row.flag);
+		assert_eq!(&[0,2,4,6], rows.id_slice());
+		assert_eq!(&[true,true,true,true], rows.flag_slice());
+
+		rows.retain_mut(|row|{
+			*row.id+=1;
+			*row.id!=5
+		});
+		assert_eq!(&[1,3,7], rows.id_slice());
+	}
+
+	#[test]
+	fn test_retain_panic() {
+		#[derive(ColVec)]
+		struct Droppy{
+			id:u8,
+			counter:DropCounter,
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+
+		let mut droppies=DroppyColVec::new_in(Global);
+		for id in 0..6 {
+			droppies.push(Droppy{id,counter:DropCounter(&COUNT)});
+		}
+
+		let result=std::panic::catch_unwind(std::panic::AssertUnwindSafe(||{
+			droppies.retain(|droppy|{
+				if *droppy.id==3{
+					panic!("retain");
+				}
+				*droppy.id%2==0
+			});
+		}));
+		assert!(result.is_err());
+		// the rows which were not checked yet are kept
+		assert_eq!(&[0,2,3,4,5], droppies.id_slice());
+		assert_eq!(1, COUNT.load(Ordering::Relaxed));
+		drop(droppies);
+		assert_eq!(6, COUNT.load(Ordering::Relaxed));
+	}
+
+	#[test]
+	fn test_extract_if() {
+		let mut rows=rows(0..8);
+		let extracted:std::vec::Vec<u32>=rows.extract_if(1..7,|row|*row.flag).map(|row|row.id).collect();
+		assert_eq!(&[2,4,6], extracted.as_slice());
+		assert_eq!(&[0,1,3,5,7], rows.id_slice());
+
+		// stopping early keeps the rest
+		let mut extract_if=rows.extract_if(..,|row|*row.id>2);
+		assert_eq!(Some(row(3)), extract_if.next());
+		drop(extract_if);
+		assert_eq!(&[0,1,5,7], rows.id_slice());
+		assert_eq!(&[true,false,false,false], rows.flag_slice());
+	}
+
+	#[test]
+	fn test_extend() {
+		let mut rows=rows(0..2);
+		rows.extend((2..5).map(row));
+		assert_eq!(&[0,1,2,3,4], rows.id_slice());
+		assert!(5<=rows.capacity());
+
+		// an inaccurate size_hint still grows as needed
+		rows.extend((5..20).map(row).filter(|row|row.id%3==0));
+		assert_eq!(&[0,1,2,3,4,6,9,12,15,18], rows.id_slice());
+		assert_eq!(&[true,false,true,false,true,true,false,true,false,true], rows.flag_slice());
+	}
+
+	#[test]
+	fn test_extend_copy() {
+		#[derive(ColVec,Clone,Copy)]
+		struct Point{
+			x:f32,
+			y:f32,
+		}
+		let points=[Point{x:1.0,y:2.0},Point{x:3.0,y:4.0}];
+
+		let mut colvec=PointColVec::new_in(Global);
+		colvec.extend(&points);
+		colvec.extend(points.iter().rev());
+		assert_eq!(&[1.0,3.0,3.0,1.0], colvec.x_slice());
+		assert_eq!(&[2.0,4.0,4.0,2.0], colvec.y_slice());
+	}
+
+	#[test]
+	fn test_into_vec() {
+		let vec:std::vec::Vec<Row>=rows(0..3).into();
+		assert_eq!(&[row(0),row(1),row(2)], vec.as_slice());
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn test_from() {
+		let collected:RowColVec=(0..3).map(row).collect();
+		assert_eq!(&[0,1,2], collected.id_slice());
+
+		let from_vec=RowColVec::from(std::vec![row(0),row(1)]);
+		assert_eq!(&[0,1], from_vec.id_slice());
+		assert_eq!(&[true,false], from_vec.flag_slice());
+
+		let from_array=RowColVec::from([row(3),row(4),row(5)]);
+		assert_eq!(&[3,4,5], from_array.id_slice());
+	}
+
+	#[test]
+	fn test_clone() {
+		#[derive(ColVec)]
+		struct Named{
+			id:u32,
+			name:std::string::String,
+		}
+		let mut named=NamedColVec::new_in(Global);
+		for (id,name) in [(0,"zero"),(1,"one"),(2,"two")]{
+			named.push(Named{id,name:name.into()});
+		}
+
+		let mut cloned=named.clone();
+		cloned.name_slice_mut()[0].push('!');
+		assert_eq!(&[0,1,2], cloned.id_slice());
+		assert_eq!(&["zero!","one","two"], cloned.name_slice());
+		assert_eq!(&["zero","one","two"], named.name_slice());
+	}
+
+	#[test]
+	fn test_clone_panic() {
+		/// Panics when cloned if the flag is set.
+		struct PanicOnClone(bool);
+		impl Clone for PanicOnClone {
+			fn clone(&self) -> Self {
+				if self.0 {
+					panic!("PanicOnClone");
+				}
+				PanicOnClone(false)
+			}
+		}
+		#[derive(ColVec)]
+		struct Droppy{
+			counter:DropCounter,
+			panicky:PanicOnClone,
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+
+		let mut droppies=DroppyColVec::new_in(Global);
+		for panicky in [false,false,true] {
+			droppies.push(Droppy{counter:DropCounter(&COUNT),panicky:PanicOnClone(panicky)});
+		}
+
+		let result=std::panic::catch_unwind(std::panic::AssertUnwindSafe(||droppies.clone()));
+		assert!(result.is_err());
+		// the counter column was cloned completely before the panic, and was dropped
+		assert_eq!(3, COUNT.load(Ordering::Relaxed));
+		drop(droppies);
+		assert_eq!(6, COUNT.load(Ordering::Relaxed));
+	}
+
+	#[test]
+	fn test_debug() {
+		let rows=rows(0..2);
+		assert_eq!(
+			r#"[Row { id: 0, flag: true, name: "row" }, Row { id: 1, flag: false, name: "row" }]"#,
+			std::format!("{rows:?}"),
+		);
+	}
+
+	#[test]
+	fn test_eq_hash() {
+		use core::hash::BuildHasher;
+		#[derive(ColVec)]
+		struct Key{
+			id:u32,
+			name:&'static str,
+		}
+		fn keys(keys:&[(u32,&'static str)])->KeyColVec<Global>{
+			let mut colvec=KeyColVec::default();
+			for &(id,name) in keys{
+				colvec.push(Key{id,name});
+			}
+			colvec
+		}
+		let a=keys(&[(0,"a"),(1,"b")]);
+		let b=keys(&[(0,"a"),(1,"b")]);
+		let c=keys(&[(0,"a"),(1,"c")]);
+		let d=keys(&[(0,"a")]);
+		assert_eq!(a,b);
+		assert_ne!(a,c);
+		assert_ne!(a,d);
+
+		let state=std::hash::RandomState::new();
+		assert_eq!(state.hash_one(&a), state.hash_one(&b));
+		assert_ne!(state.hash_one(&a), state.hash_one(&c));
+		assert_eq!(0, KeyColVec::<Global>::default().len());
+	}
+
+	/// An allocator which always fails.
+	struct FailingAlloc;
+	unsafe impl alloc::Allocator for FailingAlloc {
+		fn allocate(&self, _layout: core::alloc::Layout) -> Result<core::ptr::NonNull<[u8]>, alloc::AllocError> {
+			Err(alloc::AllocError)
+		}
+		unsafe fn deallocate(&self, _ptr: core::ptr::NonNull<u8>, _layout: core::alloc::Layout) {
+			unreachable!("nothing was allocated");
+		}
+	}
+
+	#[test]
+	fn test_try_reserve() {
+		let mut rows=rows(0..2);
+		assert_eq!(Ok(()), rows.try_reserve(10));
+		assert!(12<=rows.capacity());
+		assert_eq!(Ok(()), rows.try_reserve_exact(20));
+		assert!(22<=rows.capacity());
+		assert_eq!(&[0,1], rows.id_slice());
+
+		let err=rows.try_reserve(usize::MAX).unwrap_err();
+		assert_eq!(TryReserveErrorKind::CapacityOverflow, err.kind());
+		let err=rows.try_reserve_exact(usize::MAX/2).unwrap_err();
+		assert_eq!(TryReserveErrorKind::CapacityOverflow, err.kind());
+		assert_eq!(&[0,1], rows.id_slice());
+
+		let err=RowColVec::try_with_capacity_in(usize::MAX,Global).unwrap_err();
+		assert_eq!(TryReserveErrorKind::CapacityOverflow, err.kind());
+		assert!(RowColVec::try_with_capacity_in(4,Global).unwrap().capacity()>=4);
+	}
+
+	#[test]
+	fn test_try_push() {
+		let mut rows=RowColVec::new_in(Global);
+		for id in 0..10{
+			assert_eq!(Ok(()), rows.try_push(row(id)));
+		}
+		assert_eq!(10, rows.len());
+		assert_eq!(&[0,1,2,3,4,5,6,7,8,9], rows.id_slice());
+
+		let mut failing=RowColVec::new_in(FailingAlloc);
+		assert_eq!(Err(row(0)), failing.try_push(row(0)));
+		assert_eq!(0, failing.len());
+		let err=failing.try_reserve(1).unwrap_err();
+		assert!(matches!(err.kind(), TryReserveErrorKind::AllocError{..}));
+		assert!(RowColVec::try_with_capacity_in(1,FailingAlloc).is_err());
+	}
+
+	#[test]
+	fn test_shrink() {
+		let mut rows=rows(0..100);
+		let peak=rows.capacity();
+		rows.truncate(5);
+		rows.shrink_to(10);
+		assert!(10<=rows.capacity()&&rows.capacity()<peak);
+		assert_eq!(&[0,1,2,3,4], rows.id_slice());
+		assert_eq!(&[true,false,true,false,true], rows.flag_slice());
+		assert_eq!(&["row";5], rows.name_slice());
+
+		// shrinking to a larger capacity does nothing
+		let capacity=rows.capacity();
+		rows.shrink_to(capacity+1);
+		assert_eq!(capacity, rows.capacity());
+
+		rows.shrink_to_fit();
+		assert!(5<=rows.capacity()&&rows.capacity()<capacity);
+		assert_eq!(&[0,1,2,3,4], rows.id_slice());
+		assert_eq!(&[true,false,true,false,true], rows.flag_slice());
+		assert_eq!(&["row";5], rows.name_slice());
+
+		// the buffer still grows correctly after shrinking
+		rows.push(row(5));
+		assert_eq!(&[0,1,2,3,4,5], rows.id_slice());
+		assert_eq!(&[true,false,true,false,true,false], rows.flag_slice());
+
+		rows.clear();
+		rows.shrink_to_fit();
+		assert_eq!(0, rows.capacity());
+		rows.push(row(7));
+		assert_eq!(&[7], rows.id_slice());
+	}
+
+	#[test]
+	fn test_reserve_exact() {
+		let mut rows=RowColVec::new_in(Global);
+		rows.reserve_exact(3);
+		let capacity=rows.capacity();
+		assert!(3<=capacity&&capacity<3+core::mem::align_of::<Row>());
+		rows.extend((0..3).map(row));
+		rows.reserve_exact(capacity);
+		assert!(3+capacity<=rows.capacity());
+		assert_eq!(&[0,1,2], rows.id_slice());
+		assert_eq!(&[true,false,true], rows.flag_slice());
+	}
+
+	/// An allocator which counts its allocations, and refuses to grow a block.
+	struct ScatterOnly(AtomicUsize);
+	unsafe impl alloc::Allocator for ScatterOnly {
+		fn allocate(&self, layout: core::alloc::Layout) -> Result<core::ptr::NonNull<[u8]>, alloc::AllocError> {
+			self.0.fetch_add(1, Ordering::Relaxed);
+			alloc::Allocator::allocate(&Global, layout)
+		}
+		unsafe fn deallocate(&self, ptr: core::ptr::NonNull<u8>, layout: core::alloc::Layout) {
+			unsafe{alloc::Allocator::deallocate(&Global, ptr, layout)}
+		}
+		unsafe fn grow(&self, _ptr: core::ptr::NonNull<u8>, _old_layout: core::alloc::Layout, _new_layout: core::alloc::Layout) -> Result<core::ptr::NonNull<[u8]>, alloc::AllocError> {
+			unreachable!("the columns should be scattered into a fresh allocation");
+		}
+	}
+
+	#[test]
+	fn test_grow_large() {
+		// every growth scatters the columns into a fresh allocation, however large the table is
+		const LEN:u32=10000;
+		let mut rows=RowColVec::new_in(ScatterOnly(AtomicUsize::new(0)));
+		rows.extend((0..LEN).map(row));
+		for _ in 0..3 {
+			let capacity=rows.capacity();
+			rows.reserve_exact(capacity+1);
+		}
+		assert_eq!(4, rows.allocator().0.load(Ordering::Relaxed));
+		assert!(core::iter::zip(0..LEN, rows.id_slice()).all(|(id,&row_id)|id==row_id));
+		assert!(core::iter::zip(0..LEN, rows.flag_slice()).all(|(id,&flag)|id.is_multiple_of(2)==flag));
+		assert!(rows.name_slice().iter().all(|&name|name=="row"));
+	}
+
+	fn sum_ids(rows:&RowColSlice)->u32{
+		rows.id_slice().iter().sum()
+	}
+	fn bump_ids(rows:&mut RowColSlice){
+		for id in rows.id_slice_mut(){
+			*id+=1;
+		}
+	}
+
+	#[test]
+	fn test_col_slice() {
+		let rows=rows(0..6);
+		// a ColVec derefs to a ColSlice, like a Vec derefs to a slice
+		assert_eq!(15, sum_ids(&rows));
+		let slice:&RowColSlice=&rows;
+		assert_eq!(6, slice.len());
+		assert_eq!(5, sum_ids(&slice.slice(2..4)));
+		assert_eq!(Some(3), slice.get(3).map(|row|*row.id));
+		assert!(slice.get(6).is_none());
+
+		let (left,right)=slice.split_at(2);
+		assert_eq!(&[0,1], left.id_slice());
+		assert_eq!(&[2,3,4,5], right.id_slice());
+		assert_eq!(&[true,false,true,false], right.flag_slice());
+		assert!(slice.split_at(6).1.is_empty());
+
+		let middle=slice.slice(1..4);
+		assert_eq!(&[1,2,3], middle.id_slice());
+		assert_eq!(&[2], middle.slice(1..=1).id_slice());
+		assert_eq!(std::vec![1,2,3], middle.iter().map(|row|*row.id).collect::<std::vec::Vec<_>>());
+		assert_eq!(std::vec![4,5], (&slice.slice(4..)).into_iter().map(|row|*row.id).collect::<std::vec::Vec<_>>());
+		assert_eq!("[Row { id: 1, flag: false, name: \"row\" }]", std::format!("{:?}", slice.slice(1..2)));
+
+		// the cached column pointers keep the auto traits of the buffer
+		fn send_sync<T:Send+Sync+?Sized>(){}
+		send_sync::<RowColVec<Global>>();
+		send_sync::<RowColSlice>();
+	}
+
+	#[test]
+	fn test_col_slice_mut() {
+		let mut rows=rows(0..6);
+		bump_ids(&mut rows);
+		bump_ids(&mut rows.slice_mut(1..));
+		assert_eq!(&[1,3,4,5,6,7], rows.id_slice());
+		rows.id_slice_mut().copy_from_slice(&[0,1,2,3,4,5]);
+
+		let slice:&mut RowColSlice=&mut rows;
+		assert_eq!(15, sum_ids(slice));
+		// a mutable sub-range derefs to a shared slice too
+		assert_eq!(12, sum_ids(&slice.slice_mut(3..)));
+
+		let (mut left,mut right)=slice.split_at_mut(3);
+		left.id_slice_mut()[0]=10;
+		right.flag_slice_mut().fill(true);
+		*right.get_mut(2).unwrap().name="last";
+		assert!(right.get_mut(3).is_none());
+
+		for row in slice.slice_mut(1..3).iter_mut(){
+			*row.id+=100;
+		}
+		for row in &mut slice.slice_mut(..1){
+			*row.id+=1;
+		}
+		assert_eq!(&[11,101,102,3,4,5], rows.id_slice());
+		assert_eq!(&[true,false,true,true,true,true], rows.flag_slice());
+		assert_eq!(&["row","row","row","row","row","last"], rows.name_slice());
+	}
+
+	#[test]
+	#[should_panic = "mid > len"]
+	fn test_col_slice_split_at_out_of_bounds() {
+		let rows=rows(0..2);
+		rows.split_at(3);
+	}
+
+	#[test]
+	fn test_columns() {
+		let mut rows=rows(0..5);
+		let columns=rows.columns();
+		assert_eq!(&[0,1,2,3,4], columns.id);
+		assert_eq!(&[true,false,true,false,true], columns.flag);
+
+		// sort one column while reading another
+		let RowColumnsMut{id,flag,name}=rows.columns_mut();
+		id.sort_by_key(|&id|core::cmp::Reverse(id));
+		for (name,&flag) in name.iter_mut().zip(flag.iter()){
+			if flag{
+				*name="even";
+			}
+		}
+		assert_eq!(&[4,3,2,1,0], rows.id_slice());
+		assert_eq!(&["even","row","even","row","even"], rows.name_slice());
+
+		let mut tail=rows.slice_mut(3..);
+		let columns=tail.columns_mut();
+		columns.id.copy_from_slice(&[7,8]);
+		assert_eq!(&[false,true], rows.slice(3..).columns().flag);
+		assert_eq!(&[4,3,2,7,8], rows.id_slice());
+	}
+
+	#[test]
+	fn test_sort() {
+		// 37 is coprime with 50, so this visits every id once in a scrambled order
+		let mut rows=rows((0..50).map(|i|i*37%50));
+		assert!(!rows.is_sorted_by_key(|row|*row.id));
+		rows.sort_by_key(|row|*row.id);
+		assert!(rows.is_sorted_by_key(|row|*row.id));
+		assert!(core::iter::zip(0.., rows.id_slice()).all(|(id,&row_id)|id==row_id));
+		assert!(core::iter::zip(0u32.., rows.flag_slice()).all(|(id,&flag)|id.is_multiple_of(2)==flag));
+
+		rows.sort_unstable_by(|a,b|b.id.cmp(a.id));
+		assert!(rows.is_sorted_by_key(|row|core::cmp::Reverse(*row.id)));
+		assert!(rows.iter().all(|row|row.id.is_multiple_of(2)==*row.flag));
+
+		rows.sort_by_cached_key(|row|*row.id%10);
+		assert_eq!(&[40,30,20,10,0,41,31,21,11,1], &rows.id_slice()[..10]);
+
+		// the stable sort keeps equal rows in order
+		rows.sort_by(|a,b|b.flag.cmp(a.flag));
+		assert_eq!(&[40,30,20,10,0,42,32], &rows.id_slice()[..7]);
+		assert_eq!(&[true;25], &rows.flag_slice()[..25]);
+		assert_eq!(&[false;25], &rows.flag_slice()[25..]);
+	}
+
+	#[test]
+	fn test_binary_search() {
+		let rows=rows([1,3,3,5,8]);
+		assert_eq!(Ok(0), rows.binary_search_by_key(&1,|row|*row.id));
+		assert_eq!(Ok(3), rows.binary_search_by_key(&5,|row|*row.id));
+		assert!(matches!(rows.binary_search_by_key(&3,|row|*row.id), Ok(1|2)));
+		assert_eq!(Err(0), rows.binary_search_by_key(&0,|row|*row.id));
+		assert_eq!(Err(4), rows.binary_search_by_key(&6,|row|*row.id));
+		assert_eq!(Err(5), rows.binary_search_by_key(&9,|row|*row.id));
+		assert_eq!(Ok(4), rows.binary_search_by(|row|row.id.cmp(&8)));
+		assert_eq!(Err(0), RowColVec::new_in(Global).binary_search_by_key(&0,|row|*row.id));
+	}
+
+	#[test]
+	fn test_gather() {
+		let rows=rows(0..5);
+		let gathered=rows.gather(&[4,0,0,2]);
+		assert_eq!(&[4,0,0,2], gathered.id_slice());
+		assert_eq!(&[true,true,true,true], gathered.flag_slice());
+		assert_eq!(0, rows.gather(&[]).len());
+	}
+
+	#[test]
+	#[should_panic = "gather index (is 5) should be < len (is 5)"]
+	fn test_gather_out_of_bounds() {
+		rows(0..5).gather(&[1,5]);
+	}
+
+	#[test]
+	fn test_scatter() {
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+		#[derive(ColVec)]
+		struct Counted{
+			id:u32,
+			counter:DropCounter,
+		}
+		let counted=|id|Counted{id,counter:DropCounter(&COUNT)};
+		let mut rows=CountedColVec::new_in(Global);
+		rows.extend((0..5).map(counted));
+		let mut source=CountedColVec::new_in(Global);
+		source.extend([counted(10),counted(30)]);
+		rows.scatter(&[1,3],source);
+		assert_eq!(&[0,10,2,30,4], rows.id_slice());
+		// the replaced rows were dropped
+		assert_eq!(2, COUNT.load(Ordering::Relaxed));
+		drop(rows);
+		assert_eq!(7, COUNT.load(Ordering::Relaxed));
+	}
+
+	#[test]
+	fn test_apply_permutation() {
+		let mut rows=rows(0..6);
+		let mut permutation=[3,0,4,1,5,2];
+		rows.apply_permutation(&mut permutation);
+		assert_eq!([3,0,4,1,5,2], permutation);
+		assert_eq!(&[3,0,4,1,5,2], rows.id_slice());
+		assert_eq!(&[false,true,true,false,false,true], rows.flag_slice());
+
+		// the inverse permutation puts the rows back
+		rows.apply_permutation(&mut [1,3,5,0,2,4]);
+		assert_eq!(&[0,1,2,3,4,5], rows.id_slice());
+		assert_eq!(&[true,false,true,false,true,false], rows.flag_slice());
+	}
+
+	#[test]
+	fn test_apply_permutation_invalid() {
+		// [1,2,1] marks two rows before it finds the duplicate, which must be unmarked again
+		for mut permutation in [[0,1,1],[1,2,1],[0,1,3],[usize::MAX,1,2],[!1,0,2]]{
+			let original=permutation;
+			let mut rows=rows(0..3);
+			let result=std::panic::catch_unwind(std::panic::AssertUnwindSafe(||rows.apply_permutation(&mut permutation)));
+			assert!(result.is_err());
+			assert_eq!(original, permutation);
+			assert_eq!(&[0,1,2], rows.id_slice());
+		}
+	}
+
+	// the parameter names collide with the ones used by the generated items
+	#[derive(ColVec,Clone,Debug,PartialEq)]
+	struct Particle<'a, F: Copy, const N: usize, A = u8>
+	where
+		A: Clone,
+	{
+		position: [F; N],
+		name: &'a str,
+		tag: A,
+	}
+
+	#[test]
+	fn test_generic() {
+		let name=std::string::String::from("particle");
+		let particle=|x:f32,tag|Particle{position:[x,-x],name:&name,tag};
+		let mut particles=ParticleColVec::<'_,f32,2,u8,Global>::new_in(Global);
+		particles.extend([particle(2.0,2),particle(1.0,1)]);
+		particles.push(particle(3.0,3));
+		assert_eq!(&[[2.0,-2.0],[1.0,-1.0],[3.0,-3.0]], particles.position_slice());
+		assert_eq!(Some(particle(3.0,3)), particles.pop());
+
+		particles.sort_by_key(|row|*row.tag);
+		assert_eq!(&[1,2], particles.tag_slice());
+		assert_eq!(&["particle";2], particles.name_slice());
+
+		let clone=particles.clone();
+		assert_eq!(clone, particles);
+		assert_eq!(std::format!("{:?}", clone), "[Particle { position: [1.0, -1.0], name: \"particle\", tag: 1 }, Particle { position: [2.0, -2.0], name: \"particle\", tag: 2 }]");
+		let slice=particles.as_col_slice();
+		assert_eq!(Some(&2), slice.get(1).map(|row|row.tag));
+		let tags:std::vec::Vec<u8>=particles.drain(..).map(|particle|particle.tag).collect();
+		assert_eq!(std::vec![1,2], tags);
+	}
+
+	#[test]
+	fn test_tuple_struct() {
+		#[derive(ColVec)]
+		#[derive(Debug, PartialEq)]
+		struct Vertex(u32, f32, #[colvec(name = "weight")] f32);
+
+		#[cfg(feature = "std")]
+		let mut vertices=VertexColVec::new();
+		#[cfg(not(feature = "std"))]
+		let mut vertices=VertexColVec::new_in(global::Global);
+
+		vertices.push(Vertex(0,1.0,0.5));
+		vertices.push(Vertex(1,2.0,0.25));
+		vertices.push(Vertex(2,3.0,0.125));
+		assert_eq!(&[0,1,2], vertices.col0_slice());
+		assert_eq!(&[1.0,2.0,3.0], vertices.col1_slice());
+		assert_eq!(&[0.5,0.25,0.125], vertices.weight_slice());
+
+		vertices.col1_slice_mut()[0]=4.0;
+		*vertices.get_mut(1).unwrap().2=1.0;
+		assert_eq!(Some(&4.0), vertices.get(0).map(|row|row.1));
+		assert_eq!(Some(Vertex(2,3.0,0.125)), vertices.pop());
+
+		let VertexColumnsMut(ids,_,weights)=vertices.columns_mut();
+		ids.reverse();
+		weights.fill(0.0);
+		assert_eq!(&[1,0], vertices.columns().0);
+		assert_eq!(std::format!("{:?}", vertices), "[Vertex(1, 4.0, 0.0), Vertex(0, 2.0, 0.0)]");
+	}
+
+	#[test]
+	fn test_enum() {
+		#[derive(ColVec)]
+		#[derive(Debug, PartialEq)]
+		enum Event {
+			KeyPress { code: u32, repeat: bool },
+			Resize(u16, u16),
+			#[colvec(name = "input")]
+			Text(std::string::String),
+			Quit,
+		}
+
+		#[cfg(feature = "std")]
+		let mut events=EventColVec::new();
+		#[cfg(not(feature = "std"))]
+		let mut events=EventColVec::new_in(global::Global);
+
+		events.extend([
+			Event::KeyPress{code:13,repeat:false},
+			Event::Resize(640,480),
+			Event::Text("hi".into()),
+		]);
+		events.push(Event::Quit);
+		assert_eq!(&[EventTag::KeyPress,EventTag::Resize,EventTag::Text,EventTag::Quit], events.tags_slice());
+
+		assert_eq!(Some(&(13,false)), events.get_key_press(0));
+		assert_eq!(None, events.get_resize(0));
+		assert_eq!(Some(&(640,480)), events.get_resize(1));
+		assert_eq!(Some(&()), events.get_quit(3));
+		assert_eq!(None, events.get_quit(4));
+		events.get_input_mut(2).unwrap().0.push('!');
+		events.get_key_press_mut(0).unwrap().1=true;
+
+		for event in events.iter_mut(){
+			if let EventMut::Resize(width,_)=event {
+				*width*=2;
+			}
+		}
+		assert!(matches!(events.iter().nth(1), Some(EventRef::Resize(&1280,&480))));
+		assert!(matches!(events.iter().next_back(), Some(EventRef::Quit)));
+		assert_eq!(4, (&events).into_iter().len());
+		let cloned=events.clone();
+		assert_eq!(r#"[KeyPress { code: 13, repeat: true }, Resize(1280, 480), Text("hi!"), Quit]"#, std::format!("{cloned:?}"));
+		assert_eq!(std::format!("{events:?}"), std::format!("{cloned:?}"));
+		drop(cloned);
+
+		assert_eq!(Some(Event::Quit), events.pop());
+		assert_eq!(Some(Event::Text("hi!".into())), events.pop());
+		events.truncate(1);
+		assert_eq!(Some(Event::KeyPress{code:13,repeat:true}), events.pop());
+		assert_eq!(None, events.pop());
+		assert!(events.is_empty());
+
+		#[derive(ColVec)]
+		enum Either<'a, L, R: Copy> {
+			Left(&'a L),
+			Right(R),
+		}
+		let left=1u64;
+		let mut eithers=EitherColVec::new_in(Global);
+		eithers.push(Either::Left(&left));
+		eithers.push(Either::<'_, u64, u8>::Right(2));
+		assert_eq!(Some(&(&1,)), eithers.get_left(0));
+		assert_eq!(Some(&(2,)), eithers.get_right(1));
+	}
+
+	#[test]
+	fn test_enum_drop() {
+		#[derive(ColVec)]
+		enum Droppy {
+			Counter(DropCounter),
+			Panicky{panicky:PanicOnDrop},
+			Empty,
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+
+		#[cfg(feature = "std")]
+		let mut droppies=DroppyColVec::new();
+		#[cfg(not(feature = "std"))]
+		let mut droppies=DroppyColVec::new_in(global::Global);
+		droppies.push(Droppy::Counter(DropCounter(&COUNT)));
+		droppies.push(Droppy::Empty);
+		droppies.push(Droppy::Counter(DropCounter(&COUNT)));
+		droppies.clear();
+		assert_eq!(2, COUNT.load(Ordering::Relaxed));
+
+		droppies.push(Droppy::Counter(DropCounter(&COUNT)));
+		droppies.push(Droppy::Panicky{panicky:PanicOnDrop(true)});
+		droppies.push(Droppy::Counter(DropCounter(&COUNT)));
+		let result=std::panic::catch_unwind(std::panic::AssertUnwindSafe(||drop(droppies)));
+		assert!(result.is_err());
+		// the rows after the panicking one are still dropped
+		assert_eq!(4, COUNT.load(Ordering::Relaxed));
+	}
+
+	#[test]
+	fn test_enum_clone_panic() {
+		/// Panics when cloned if the flag is set.
+		struct PanicOnClone(bool);
+		impl Clone for PanicOnClone {
+			fn clone(&self) -> Self {
+				if self.0 {
+					panic!("PanicOnClone");
+				}
+				PanicOnClone(false)
+			}
+		}
+		#[derive(ColVec,Clone)]
+		enum Droppy {
+			Counter(DropCounter),
+			Panicky(PanicOnClone),
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+
+		let mut droppies=DroppyColVec::new_in(Global);
+		droppies.push(Droppy::Counter(DropCounter(&COUNT)));
+		droppies.push(Droppy::Counter(DropCounter(&COUNT)));
+		droppies.push(Droppy::Panicky(PanicOnClone(true)));
+		droppies.push(Droppy::Counter(DropCounter(&COUNT)));
+
+		let result=std::panic::catch_unwind(std::panic::AssertUnwindSafe(||droppies.clone()));
+		assert!(result.is_err());
+		// the rows before the panicking one were cloned, and were dropped
+		assert_eq!(2, COUNT.load(Ordering::Relaxed));
+		drop(droppies);
+		assert_eq!(5, COUNT.load(Ordering::Relaxed));
+	}
+
+	mod bank {
+		use ::colvec::ColVec;
+
+		#[derive(ColVec)]
+		pub struct Account {
+			pub id: u32,
+			balance: u64,
+			#[colvec(vis = "pub", name = "holder")]
+			owner: &'static str,
+		}
+		pub fn open(id: u32, owner: &'static str) -> Account {
+			Account { id, balance: 100, owner }
+		}
+		pub fn total<A: ::colvec::alloc::Allocator>(accounts: &AccountColVec<A>) -> u64 {
+			accounts.balance_slice().iter().sum()
+		}
+	}
+
+	#[test]
+	fn test_column_visibility() {
+		let mut accounts=bank::AccountColVec::new_in(Global);
+		accounts.push(bank::open(0,"alice"));
+		accounts.push(bank::open(1,"bob"));
+		assert_eq!(&[0,1], accounts.id_slice());
+		assert_eq!(&["alice","bob"], accounts.holder_slice());
+		assert_eq!(&["alice","bob"], accounts.columns().owner);
+		assert_eq!(200, bank::total(&accounts));
+	}
+
+	fn push_all<T: Columnar, A: ::colvec::alloc::Allocator>(colvec: &mut ColVec<T, A>, values: impl IntoIterator<Item = T>) -> usize {
+		colvec.extend(values);
+		colvec.len()
+	}
+
+	#[test]
+	fn test_columnar() {
+		let mut rows=ColVec::<Row,Global>::new_in(Global);
+		assert_eq!(3, push_all(&mut rows,[row(0),row(1),row(2)]));
+		assert_eq!(&[0,1,2], rows.id_slice());
+		assert_eq!(Some(&1), rows.get(1).map(|row|row.id));
+		*rows.get_mut(2).unwrap().id=5;
+		assert_eq!(&[0,1,5], rows.columns().id);
+		rows.columns_mut().flag.fill(true);
+		assert_eq!(Some(5), rows.pop().map(|row|row.id));
+
+		let inner:RowColVec<Global>=rows.into_inner();
+		assert_eq!(&[true,true], inner.flag_slice());
+		assert_eq!(2, ColVec::<Row,Global>::from_inner(inner).len());
+		assert_eq!(<Row as colvec::raw::StructInfo<3>>::LAYOUT, <Row as Columnar>::LAYOUT);
+		assert_eq!(&[core::alloc::Layout::new::<u32>(),core::alloc::Layout::new::<bool>(),core::alloc::Layout::new::<&str>()], Row::FIELD_LAYOUTS);
+
+		#[derive(ColVec)]
+		#[derive(Debug, PartialEq)]
+		enum Shape {
+			Circle { radius: f32 },
+			Rect(u16, u16),
+			Empty,
+		}
+		let mut shapes=ColVec::<Shape,Global>::new_in(Global);
+		assert_eq!(3, push_all(&mut shapes,[Shape::Circle{radius:1.0},Shape::Rect(2,3),Shape::Empty]));
+		assert_eq!(&[ShapeTag::Circle,ShapeTag::Rect,ShapeTag::Empty], shapes.columns());
+		assert!(matches!(shapes.get(0), Some(ShapeRef::Circle{radius:&1.0})));
+		if let Some(ShapeMut::Rect(width,_))=shapes.get_mut(1) {
+			*width=4;
+		}
+		assert_eq!("Some(Rect(4, 3))", std::format!("{:?}", shapes.get(1)));
+		assert!(matches!(shapes.get(2), Some(ShapeRef::Empty)));
+		assert!(shapes.get(3).is_none());
+		assert_eq!(Some(Shape::Empty), shapes.pop());
+		assert_eq!(2, Shape::FIELD_LAYOUTS.len());
+
+		#[derive(ColVec)]
+		enum Unit {
+			A,
+			B,
+		}
+		let mut units=ColVec::<Unit,Global>::new_in(Global);
+		units.extend([Unit::A,Unit::B]);
+		assert!(matches!(units.get(1), Some(UnitRef::B)));
+		assert_eq!(&[UnitTag::A,UnitTag::B], units.columns());
+	}
+
+	#[test]
+	fn test_fields_from_sizes() {
+		use core::alloc::Layout;
+		// older generated code describes its fields by size alone
+		const SIZES:fields::Fields<3>=fields::Fields::from_sizes([4,1,8]);
+		let layouts=fields::Fields::from_layouts([Layout::new::<u32>(),Layout::new::<bool>(),Layout::new::<f64>()]);
+		assert_eq!(layouts.size(), SIZES.size());
+		for index in 0..3 {
+			assert_eq!(layouts.offset_of(index), SIZES.offset_of(index));
+			assert_eq!(1, SIZES.layout_of(index).align());
+		}
+	}
+
+	#[test]
+	fn test_dyn_colvec() {
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+		let columns=[
+			DynColumn::of::<u8>(),
+			DynColumn::of::<u64>(),
+			DynColumn::of::<DropCounter>(),
+			DynColumn::from_layout(core::alloc::Layout::new::<[u16;3]>()),
+		];
+		let mut table=DynColVec::new_in(columns,Global);
+		assert_eq!(4, table.columns().len());
+		for i in 0..100u64 {
+			let small=i as u8;
+			let counter=core::mem::ManuallyDrop::new(DropCounter(&COUNT));
+			let untyped=[i as u16;3];
+			unsafe{table.push_raw(&[
+				(&raw const small).cast(),
+				(&raw const i).cast(),
+				(&raw const counter).cast(),
+				(&raw const untyped).cast(),
+			])};
+		}
+		assert_eq!(100, table.len());
+		assert!(100<=table.capacity());
+		assert_eq!(Some(&[0,1,2][..]), table.column::<u64>(1).map(|column|&column[..3]));
+		assert_eq!(Some(99), table.column::<u8>(0).map(|column|column[99]));
+		assert_eq!(None, table.column::<u32>(1));
+		assert_eq!(None, table.column::<u16>(3));
+		assert_eq!(None, table.column::<u8>(4));
+		assert_eq!(100*6, table.column_uninit_bytes(3).len());
+		assert_eq!(&[2,0,2,0,2,0], unsafe{&table.column_bytes(3)[12..18]});
+
+		table.swap_remove(0);
+		assert_eq!(1, COUNT.load(Ordering::Relaxed));
+		assert_eq!(Some(&[99,1,2][..]), table.column::<u64>(1).map(|column|&column[..3]));
+
+		let mut small=0u8;
+		let mut big=0u64;
+		let mut counter=core::mem::MaybeUninit::<DropCounter>::uninit();
+		let mut untyped=[0u16;3];
+		unsafe{table.swap_remove_raw(1,&[
+			(&raw mut small).cast(),
+			(&raw mut big).cast(),
+			counter.as_mut_ptr().cast(),
+			(&raw mut untyped).cast(),
+		])};
+		assert_eq!((1,1,[1;3]), (small,big,untyped));
+		drop(unsafe{counter.assume_init()});
+		assert_eq!(2, COUNT.load(Ordering::Relaxed));
+		assert_eq!(Some(&[99,98,2][..]), table.column::<u64>(1).map(|column|&column[..3]));
+
+		table.column_mut::<u64>(1).unwrap()[0]=7;
+		assert_eq!(Some(7), table.column::<u64>(1).map(|column|column[0]));
+		table.truncate(50);
+		assert_eq!(2+48, COUNT.load(Ordering::Relaxed));
+		drop(table);
+		assert_eq!(100, COUNT.load(Ordering::Relaxed));
+	}
+
+	#[test]
+	fn test_dyn_row() {
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+		let columns=[DynColumn::of::<u32>(),DynColumn::of::<DropCounter>()];
+		let mut table=DynColVec::new_in(columns,Global);
+		table.push_row().with(1u32).with(DropCounter(&COUNT)).finish();
+		table.push_row().with(2u32).with(DropCounter(&COUNT)).finish();
+		assert_eq!(Some(&[1,2][..]), table.column::<u32>(0));
+
+		// a row which is not finished drops its values
+		let _=table.push_row().with(3u32).with(DropCounter(&COUNT));
+		assert_eq!(1, COUNT.load(Ordering::Relaxed));
+		let result=std::panic::catch_unwind(std::panic::AssertUnwindSafe(||{table.push_row().with(4u32).with(5u32);}));
+		assert!(result.is_err());
+		let result=std::panic::catch_unwind(std::panic::AssertUnwindSafe(||table.push_row().with(4u32).finish()));
+		assert!(result.is_err());
+		assert_eq!(2, table.len());
+		drop(table);
+		assert_eq!(3, COUNT.load(Ordering::Relaxed));
+
+		fn send_sync<T:Send+Sync>(_:&T){}
+		let table=unsafe{DynColVec::new_send_sync_in([DynColumn::of::<u32>()],Global)};
+		send_sync(&table);
+	}
+
+	#[derive(ColVec,Clone)]
+	#[colvec(storage = "per_column")]
+	struct Sample{
+		id:u32,
+		alive:bool,
+		position:[f64;3],
+		counter:DropCounter,
+	}
+
+	#[test]
+	fn test_per_column_storage() {
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+		let sample=|id|Sample{id,alive:id.is_multiple_of(3),position:[id as f64;3],counter:DropCounter(&COUNT)};
+
+		let mut samples=SampleColVec::new_in(Global);
+		for id in 0..20 {
+			samples.push(sample(id));
+		}
+		assert!(20<=samples.capacity());
+		assert!(core::iter::zip(0.., samples.id_slice()).all(|(id,&sample_id)|id==sample_id));
+		assert_eq!(Some(&[19.0;3]), samples.position_slice().last());
+		assert!(samples.position_slice().as_ptr().is_aligned());
+
+		assert_eq!(Some(19), samples.pop().map(|sample|sample.id));
+		assert_eq!(1, samples.remove(1).id);
+		assert_eq!(0, samples.swap_remove(0).id);
+		samples.insert(0,sample(100));
+		assert_eq!(&[100,18,2,3], &samples.id_slice()[..4]);
+
+		samples.retain(|sample|!*sample.alive);
+		assert!(samples.alive_slice().iter().all(|&alive|!alive));
+		samples.sort_by_key(|sample|*sample.id);
+		assert!(samples.id_slice().is_sorted());
+		samples.splice(1..3,[sample(50),sample(51),sample(52)]);
+		assert_eq!(&[2,50,51,52,7], &samples.id_slice()[..5]);
+
+		let mut other=SampleColVec::new_in(Global);
+		other.push(sample(200));
+		samples.append(&mut other);
+		assert_eq!(Some(&200), samples.id_slice().last());
+
+		let cloned=samples.clone();
+		assert_eq!(cloned.id_slice(), samples.id_slice());
+		assert_eq!(cloned.position_slice(), samples.position_slice());
+		let cloned_len=cloned.len();
+		drop(cloned);
+		samples.shrink_to_fit();
+		assert_eq!(samples.len(), samples.capacity());
+		assert_eq!(Some(&[50.0;3]), samples.position_slice().get(1));
+
+		drop(samples);
+		// every sample made or cloned is dropped exactly once
+		assert_eq!(20+1+3+1+cloned_len, COUNT.load(Ordering::Relaxed));
+	}
+
+	/// An allocator which fails every allocation after the first `remaining`.
+	struct FailAfter(AtomicUsize);
+	unsafe impl alloc::Allocator for FailAfter {
+		fn allocate(&self, layout: core::alloc::Layout) -> Result<core::ptr::NonNull<[u8]>, alloc::AllocError> {
+			self.0.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |remaining|remaining.checked_sub(1)).map_err(|_|alloc::AllocError)?;
+			alloc::Allocator::allocate(&Global, layout)
+		}
+		unsafe fn deallocate(&self, ptr: core::ptr::NonNull<u8>, layout: core::alloc::Layout) {
+			unsafe{alloc::Allocator::deallocate(&Global, ptr, layout)}
+		}
+	}
+
+	#[test]
+	fn test_per_column_try_reserve_fails() {
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+		let sample=|id|Sample{id,alive:true,position:[id as f64;3],counter:DropCounter(&COUNT)};
+		// room for the four columns, and two of the next four
+		let alloc=FailAfter(AtomicUsize::new(4+2));
+		let mut samples=SampleColVec::with_capacity_in(4,&alloc);
+		for id in 0..4 {
+			samples.push(sample(id));
+		}
+
+		// the third column fails to grow, and the columns are left as they were
+		let err=samples.try_reserve(1).unwrap_err();
+		assert!(matches!(err.kind(), TryReserveErrorKind::AllocError{..}));
+		let err=samples.try_reserve_exact(1).unwrap_err();
+		assert!(matches!(err.kind(), TryReserveErrorKind::AllocError{..}));
+		assert_eq!(4, samples.capacity());
+		assert_eq!(&[0,1,2,3], samples.id_slice());
+		assert_eq!(Some(&[3.0;3]), samples.position_slice().last());
+
+		alloc.0.store(4, Ordering::Relaxed);
+		assert_eq!(Ok(()), samples.try_reserve_exact(4));
+		assert_eq!(8, samples.capacity());
+		samples.push(sample(4));
+		assert_eq!(&[0,1,2,3,4], samples.id_slice());
+		drop(samples);
+		assert_eq!(5, COUNT.load(Ordering::Relaxed));
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn test_columns_owned() {
+		let rows=RowColVec::from_columns(std::vec![0,1,2],std::vec![true,false,true],std::vec!["a","b","c"]);
+		assert_eq!(&[0,1,2], rows.id_slice());
+		assert_eq!(Some(&"b"), rows.get(1).map(|row|row.name));
+
+		let (ids,flags,names)=rows.into_columns();
+		assert_eq!((ids.as_slice(),flags.as_slice(),names.as_slice()), (&[0,1,2][..],&[true,false,true][..],&["a","b","c"][..]));
+
+		let owned=RowColumnsOwned{id:ids,flag:flags,name:names};
+		let mut rows=RowColVec::from(owned);
+		rows.push(row(3));
+		let owned=RowColumnsOwned::from(rows);
+		assert_eq!(&[0,1,2,3], owned.id.as_slice());
+		assert_eq!(&["a","b","c","row"], owned.name.as_slice());
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn test_columns_owned_per_column() {
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+		let mut ids=std::vec::Vec::with_capacity(8);
+		ids.extend(0..3);
+		let mut alive=std::vec::Vec::with_capacity(8);
+		alive.extend([true,false,true]);
+		let mut positions=std::vec::Vec::with_capacity(8);
+		positions.extend([[0.0;3],[1.0;3],[2.0;3]]);
+		let mut counters=std::vec::Vec::with_capacity(8);
+		counters.extend((0..3).map(|_|DropCounter(&COUNT)));
+		let ids_ptr=ids.as_ptr();
+
+		// columns with the same capacity are adopted without copying
+		let mut samples=SampleColVec::from_columns(ids,alive,positions,counters);
+		assert_eq!(ids_ptr, samples.id_slice().as_ptr());
+		assert_eq!(8, samples.capacity());
+		samples.push(Sample{id:3,alive:false,position:[3.0;3],counter:DropCounter(&COUNT)});
+
+		let (ids,_,positions,counters)=samples.into_columns();
+		assert_eq!(ids_ptr, ids.as_ptr());
+		assert_eq!(&[0,1,2,3], ids.as_slice());
+		assert_eq!(Some(&[3.0;3]), positions.last());
+		assert_eq!(0, COUNT.load(Ordering::Relaxed));
+
+		// otherwise they are copied into fresh columns
+		let samples=SampleColVec::from_columns(ids,std::vec![false;4],positions,counters);
+		assert_eq!(&[false;4], samples.alive_slice());
+		drop(samples);
+		assert_eq!(4, COUNT.load(Ordering::Relaxed));
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	#[should_panic(expected = "every column should have the same length, but the lengths are [2, 1, 2]")]
+	fn test_columns_owned_length_mismatch() {
+		RowColVec::from_columns(std::vec![0,1],std::vec![true],std::vec!["a","b"]);
+	}
+
+	#[test]
+	fn test_array_colvec() {
+		let mut rows=RowArrayColVec::<4>::new();
+		assert_eq!(4, rows.capacity());
+		for id in 0..4 {
+			rows.push(row(id));
+		}
+		assert!(rows.is_full());
+		assert_eq!(Err(row(4)), rows.try_push(row(4)));
+		assert_eq!(&[0,1,2,3], rows.id_slice());
+		assert_eq!(&[true,false,true,false], rows.flag_slice());
+
+		assert_eq!(Some(row(3)), rows.pop());
+		assert_eq!(row(0), rows.swap_remove(0));
+		assert_eq!(&[2,1], rows.id_slice());
+		*rows.get_mut(1).unwrap().id=5;
+		rows.columns_mut().name.fill("array");
+		assert_eq!(Some(&5), rows.get(1).map(|row|row.id));
+		assert_eq!(&["array","array"], rows.columns().name);
+		assert_eq!(std::vec![2,5], rows.iter().map(|row|*row.id).collect::<std::vec::Vec<_>>());
+		for row in rows.iter_mut(){
+			*row.flag= !*row.flag;
+		}
+		assert_eq!(&[false,true], rows.flag_slice());
+
+		let cloned=rows.clone();
+		assert_eq!(cloned.id_slice(), rows.id_slice());
+		assert_eq!(std::format!("{cloned:?}"), std::format!("{rows:?}"));
+		rows.clear();
+		assert!(rows.is_empty());
+		rows.extend([row(7),row(8)]);
+		assert_eq!(&[7,8], rows.id_slice());
+	}
+
+	#[test]
+	fn test_array_colvec_drop() {
+		#[derive(ColVec,Clone)]
+		struct Droppy{
+			id:u8,
+			counter:DropCounter,
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+		let droppy=|id|Droppy{id,counter:DropCounter(&COUNT)};
+
+		let mut droppies=DroppyArrayColVec::<8>::default();
+		for id in 0..6 {
+			droppies.push(droppy(id));
+		}
+		droppies.truncate(4);
+		assert_eq!(2, COUNT.load(Ordering::Relaxed));
+		let cloned=droppies.clone();
+		drop(droppies);
+		assert_eq!(2+4, COUNT.load(Ordering::Relaxed));
+		assert_eq!(&[0,1,2,3], cloned.id_slice());
+		drop(cloned);
+		assert_eq!(2+4+4, COUNT.load(Ordering::Relaxed));
+	}
+
+	#[test]
+	#[should_panic(expected = "ArrayColVec is full (capacity is 1)")]
+	fn test_array_colvec_full() {
+		let mut rows=RowArrayColVec::<1>::new();
+		rows.push(row(0));
+		rows.push(row(1));
+	}
+
+	#[test]
+	fn test_small_colvec() {
+		let mut rows=RowSmallColVec::<2,Global>::new_in(Global);
+		assert_eq!(2, rows.capacity());
+		rows.push(row(0));
+		rows.push(row(1));
+		assert!(!rows.spilled());
+		rows.push(row(2));
+		assert!(rows.spilled());
+		assert!(rows.capacity()>=3);
+		assert_eq!(&[0,1,2], rows.id_slice());
+		assert_eq!(&[true,false,true], rows.flag_slice());
+
+		assert_eq!(Some(row(2)), rows.pop());
+		assert_eq!(row(0), rows.swap_remove(0));
+		*rows.get_mut(0).unwrap().id=5;
+		rows.columns_mut().name.fill("small");
+		assert_eq!(Some(&5), rows.get(0).map(|row|row.id));
+		assert_eq!(&["small"], rows.columns().name);
+		assert_eq!(std::vec![5], rows.iter().map(|row|*row.id).collect::<std::vec::Vec<_>>());
+
+		let cloned=rows.clone();
+		assert_eq!(std::format!("{cloned:?}"), std::format!("{rows:?}"));
+		let colvec=rows.into_colvec();
+		assert_eq!(&[5], colvec.id_slice());
+
+		let mut inline=RowSmallColVec::<4,Global>::with_capacity_in(3,Global);
+		inline.extend([row(7),row(8)]);
+		assert!(!inline.spilled());
+		let colvec=inline.into_colvec();
+		assert_eq!(&[7,8], colvec.id_slice());
+		assert!(RowSmallColVec::<4,Global>::with_capacity_in(5,Global).spilled());
+	}
+
+	#[test]
+	fn test_small_colvec_drop() {
+		#[derive(ColVec,Clone)]
+		struct Droppy{
+			id:u8,
+			counter:DropCounter,
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+		let droppy=|id|Droppy{id,counter:DropCounter(&COUNT)};
+
+		let mut droppies=DroppySmallColVec::<3,Global>::default();
+		for id in 0..5 {
+			droppies.push(droppy(id));
+		}
+		// spilling moves the rows without dropping them
+		assert!(droppies.spilled());
+		assert_eq!(0, COUNT.load(Ordering::Relaxed));
+		assert_eq!(&[0,1,2,3,4], droppies.id_slice());
+		droppies.truncate(2);
+		assert_eq!(3, COUNT.load(Ordering::Relaxed));
+		drop(droppies);
+		assert_eq!(3+2, COUNT.load(Ordering::Relaxed));
+
+		let mut inline=DroppySmallColVec::<3,Global>::default();
+		inline.extend([droppy(0),droppy(1)]);
+		drop(inline);
+		assert_eq!(3+2+2, COUNT.load(Ordering::Relaxed));
+	}
 }
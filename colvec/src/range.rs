@@ -0,0 +1,59 @@
+use core::ops::{Bound, Range, RangeBounds};
+
+#[cold]
+#[track_caller]
+fn start_index_overflow_fail() -> ! {
+	panic!("attempted to index slice from after maximum usize");
+}
+
+#[cold]
+#[track_caller]
+fn end_index_overflow_fail() -> ! {
+	panic!("attempted to index slice up to maximum usize");
+}
+
+#[cold]
+#[track_caller]
+fn index_order_fail(start: usize, end: usize) -> ! {
+	panic!("slice index starts at {start} but ends at {end}");
+}
+
+#[cold]
+#[track_caller]
+fn end_index_len_fail(end: usize, len: usize) -> ! {
+	panic!("range end index {end} out of range for slice of length {len}");
+}
+
+/// Performs bounds checking of a range, like the unstable `core::slice::range`.
+///
+/// Panics if the range is out of bounds for a collection of length `len`.
+#[track_caller]
+pub fn range<R>(range: R, len: usize) -> Range<usize>
+where
+	R: RangeBounds<usize>,
+{
+	let start = match range.start_bound() {
+		Bound::Included(&start) => start,
+		Bound::Excluded(start) => {
+			start.checked_add(1).unwrap_or_else(|| start_index_overflow_fail())
+		}
+		Bound::Unbounded => 0,
+	};
+
+	let end = match range.end_bound() {
+		Bound::Included(end) => {
+			end.checked_add(1).unwrap_or_else(|| end_index_overflow_fail())
+		}
+		Bound::Excluded(&end) => end,
+		Bound::Unbounded => len,
+	};
+
+	if start > end {
+		index_order_fail(start, end);
+	}
+	if end > len {
+		end_index_len_fail(end, len);
+	}
+
+	Range { start, end }
+}
@@ -8,7 +8,8 @@ use core::ptr::NonNull;
 use crate::alloc::Allocator;
 use crate::error::TryReserveError;
 use crate::error::TryReserveErrorKind::*;
-use crate::fields::Fields;
+use crate::fields::{Field, grow_columns, move_columns, permute_columns, retain_rows, rotate_column, shrink_columns};
+pub use crate::fields::StructInfo;
 
 // why isn't this in core::alloc ???
 extern crate alloc;
@@ -36,17 +37,12 @@ pub struct RawColVec<const N:usize, T: StructInfo<N>, A: Allocator> {
 unsafe impl<const N:usize, T: Send + StructInfo<N>, A: Allocator> Send for RawColVec<N, T, A> {}
 unsafe impl<const N:usize, T: Sync + StructInfo<N>, A: Allocator> Sync for RawColVec<N, T, A> {}
 
-struct RawColVecInner<A: Allocator> {
-	ptr: NonNull<u8>,
+pub(crate) struct RawColVecInner<A: Allocator> {
+	pub(crate) ptr: NonNull<u8>,
 	cap: usize,
 	alloc: A,
 }
 
-// TODO: don't do this
-pub trait StructInfo<const N:usize> {
-	const LAYOUT:Layout;
-	const FIELDS:Fields::<N>;
-}
 
 // Tiny Vecs are dumb. Skip to:
 // - 8 if the element size is 1, because any heap allocators is likely
@@ -80,6 +76,13 @@ impl<const N:usize, T: StructInfo<N>, A: Allocator> RawColVec<N, T, A> {
 		}
 	}
 	#[inline]
+	pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+		match RawColVecInner::try_allocate_in(capacity, AllocInit::Uninitialized, alloc, T::LAYOUT) {
+			Ok(inner) => Ok(Self { inner, _marker: PhantomData }),
+			Err(e) => Err(e),
+		}
+	}
+	#[inline]
 	#[track_caller]
 	pub fn with_capacity_zeroed_in(capacity: usize, alloc: A) -> Self {
 		Self {
@@ -109,7 +112,24 @@ impl<const N:usize, T: StructInfo<N>, A: Allocator> RawColVec<N, T, A> {
 	#[inline]
 	#[track_caller]
 	pub fn reserve(&mut self, len: usize, additional: usize) {
-		self.inner.reserve(len, additional, T::LAYOUT, &T::FIELDS)
+		self.inner.reserve(len, additional, T::LAYOUT, T::FIELDS.sorted_fields())
+	}
+	#[inline]
+	#[track_caller]
+	pub fn reserve_exact(&mut self, len: usize, additional: usize) {
+		if let Err(err) = self.try_reserve_exact(len, additional) {
+			handle_error(err);
+		}
+	}
+	/// The same as `reserve`, but returns on errors instead of panicking or aborting.
+	#[inline]
+	pub fn try_reserve(&mut self, len: usize, additional: usize) -> Result<(), TryReserveError> {
+		self.inner.try_reserve(len, additional, T::LAYOUT, T::FIELDS.sorted_fields())
+	}
+	/// The same as `reserve_exact`, but returns on errors instead of panicking or aborting.
+	#[inline]
+	pub fn try_reserve_exact(&mut self, len: usize, additional: usize) -> Result<(), TryReserveError> {
+		self.inner.try_reserve_exact(len, additional, T::LAYOUT, T::FIELDS.sorted_fields())
 	}
 	/// Gets a raw pointer to the start of the allocation. Note that this is
 	/// `Unique::dangling()` if `capacity == 0` or `T` is zero-sized. In the former case, you must
@@ -118,10 +138,72 @@ impl<const N:usize, T: StructInfo<N>, A: Allocator> RawColVec<N, T, A> {
 	pub const fn ptr(&self) -> *mut u8 {
 		self.inner.ptr.as_ptr()
 	}
+	/// Gets a raw pointer to the start of the column for the field at `index`.
+	#[inline]
+	pub const fn field_ptr(&self, index: usize) -> *mut u8 {
+		// SAFETY: every column starts inside of (or one past the end of) the allocation
+		unsafe { self.ptr().add(self.capacity() * T::FIELDS.offset_of(index)) }
+	}
 	#[inline(never)]
 	#[track_caller]
 	pub fn grow_one(&mut self) {
-		self.inner.grow_one(T::LAYOUT,&T::FIELDS)
+		self.inner.grow_one(T::LAYOUT,T::FIELDS.sorted_fields())
+	}
+	/// Copies `count` rows from `src_index` to `dst_index` within every column.
+	///
+	/// # Safety
+	///
+	/// Both ranges of rows must be within the capacity.
+	#[inline]
+	pub const unsafe fn copy_rows(&self, src_index: usize, dst_index: usize, count: usize) {
+		unsafe { T::FIELDS.copy_fields(self.ptr(), self.capacity(), src_index, dst_index, count) }
+	}
+	/// Moves the first `count` rows of `src` into every column starting at `dst_index`.
+	///
+	/// # Safety
+	///
+	/// `src` must not be `self`, its first `count` rows must not be used again,
+	/// and `dst_index + count` must not exceed the capacity.
+	#[inline]
+	pub const unsafe fn move_rows(&self, src: &Self, dst_index: usize, count: usize) {
+		unsafe { T::FIELDS.move_fields(src.ptr(), self.ptr(), src.capacity(), self.capacity(), dst_index, count) }
+	}
+	/// Rotates the rows in `start..end` of every column so that the row at `mid` comes first.
+	///
+	/// # Safety
+	///
+	/// `start <= mid <= end`, and `end` must not exceed the capacity.
+	#[inline]
+	pub const unsafe fn rotate_rows(&self, start: usize, mid: usize, end: usize) {
+		unsafe { T::FIELDS.rotate_fields(self.ptr(), self.capacity(), start, mid, end) }
+	}
+	/// # Safety
+	///
+	/// See [`Fields::retain`](crate::fields::Fields::retain).
+	#[inline]
+	pub unsafe fn retain(&self, len: &mut usize, keep: impl FnMut(usize) -> bool, drop_row: impl FnMut(usize)) {
+		unsafe { T::FIELDS.retain(self.ptr(), self.capacity(), len, keep, drop_row) }
+	}
+	/// # Safety
+	///
+	/// See [`Fields::apply_permutation`](crate::fields::Fields::apply_permutation).
+	#[inline]
+	pub unsafe fn apply_permutation(&self, permutation: &mut [usize]) {
+		unsafe { T::FIELDS.apply_permutation(self.ptr(), self.capacity(), permutation) }
+	}
+	/// Shrinks the buffer down to the specified capacity. If the given amount
+	/// is 0, actually completely deallocates.
+	///
+	/// # Panics
+	///
+	/// Panics if the given amount is *larger* than the current capacity,
+	/// or if `len` is larger than the given amount.
+	#[track_caller]
+	#[inline]
+	pub fn shrink_to_fit(&mut self, len: usize, cap: usize) {
+		if let Err(err) = self.inner.shrink(len, cap, T::LAYOUT, T::FIELDS.sorted_fields()) {
+			handle_error(err);
+		}
 	}
 }
 
@@ -133,16 +215,295 @@ impl<const N:usize, T: StructInfo<N>, A: Allocator> Drop for RawColVec<N, T, A>
 	}
 }
 
+/// The buffer of a ColVec which gives every column its own allocation.
+///
+/// It has the same interface as [`RawColVec`], but growing reallocates each column
+/// in place of moving every column within one allocation. The capacity is shared by
+/// all of the columns, and each column is allocated with the layout of its field, so
+/// it has the same layout as the buffer of a `Vec` of the field.
+pub struct RawColumns<const N:usize, T: StructInfo<N>, A: Allocator> {
+	ptrs: [NonNull<u8>; N],
+	cap: usize,
+	alloc: A,
+	_marker: PhantomData<T>,
+}
+unsafe impl<const N:usize, T: Send + StructInfo<N>, A: Allocator> Send for RawColumns<N, T, A> {}
+unsafe impl<const N:usize, T: Sync + StructInfo<N>, A: Allocator> Sync for RawColumns<N, T, A> {}
+
+impl<const N:usize, T: StructInfo<N>, A: Allocator> RawColumns<N, T, A> {
+	#[inline]
+	pub const fn new_in(alloc: A) -> Self {
+		Self { ptrs: Self::dangling(), cap: 0, alloc, _marker: PhantomData }
+	}
+	#[inline]
+	#[track_caller]
+	pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+		match Self::try_with_capacity_in(capacity, alloc) {
+			Ok(this) => this,
+			Err(err) => handle_error(err),
+		}
+	}
+	#[inline]
+	pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+		let mut this = Self::new_in(alloc);
+		if T::LAYOUT.size() != 0 && capacity != 0 {
+			this.set_capacity(capacity)?;
+		}
+		Ok(this)
+	}
+	/// # Safety
+	///
+	/// Each pointer must be aligned for its field. Unless its field is zero-sized or
+	/// `capacity` is 0, it must be allocated by `alloc` with the layout of an array of
+	/// `capacity` values of its field, such as the buffer of a `Vec` of the field.
+	#[inline]
+	pub const unsafe fn from_raw_parts_in(ptrs: [*mut u8; N], capacity: usize, alloc: A) -> Self {
+		let mut this = Self::new_in(alloc);
+		let mut index = 0;
+		while index < N {
+			// SAFETY: Precondition passed to the caller
+			this.ptrs[index] = unsafe { NonNull::new_unchecked(ptrs[index]) };
+			index += 1;
+		}
+		this.cap = capacity;
+		this
+	}
+	// every column starts out unallocated, with a pointer aligned for its field
+	const fn dangling() -> [NonNull<u8>; N] {
+		let mut ptrs = [NonNull::dangling(); N];
+		let mut index = 0;
+		while index < N {
+			let align = T::FIELDS.layout_of(index).align();
+			ptrs[index] = NonNull::without_provenance(NonZero::new(align).unwrap());
+			index += 1;
+		}
+		ptrs
+	}
+	#[inline]
+	pub const fn capacity(&self) -> usize {
+		if T::LAYOUT.size() == 0 { usize::MAX } else { self.cap }
+	}
+	#[inline]
+	pub fn allocator(&self) -> &A {
+		&self.alloc
+	}
+	#[inline]
+	#[track_caller]
+	pub fn reserve(&mut self, len: usize, additional: usize) {
+		if let Err(err) = self.try_reserve(len, additional) {
+			handle_error(err);
+		}
+	}
+	#[inline]
+	#[track_caller]
+	pub fn reserve_exact(&mut self, len: usize, additional: usize) {
+		if let Err(err) = self.try_reserve_exact(len, additional) {
+			handle_error(err);
+		}
+	}
+	/// The same as `reserve`, but returns on errors instead of panicking or aborting.
+	#[inline]
+	pub fn try_reserve(&mut self, len: usize, additional: usize) -> Result<(), TryReserveError> {
+		if self.needs_to_grow(len, additional) {
+			self.grow_amortized(len, additional)?;
+		}
+		Ok(())
+	}
+	/// The same as `reserve_exact`, but returns on errors instead of panicking or aborting.
+	#[inline]
+	pub fn try_reserve_exact(&mut self, len: usize, additional: usize) -> Result<(), TryReserveError> {
+		if self.needs_to_grow(len, additional) {
+			let cap = len.checked_add(additional).ok_or(CapacityOverflow)?;
+			self.set_capacity(cap)?;
+		}
+		Ok(())
+	}
+	/// Gets a raw pointer to the start of the column for the field at `index`.
+	#[inline]
+	pub const fn field_ptr(&self, index: usize) -> *mut u8 {
+		self.ptrs[index].as_ptr()
+	}
+	#[inline(never)]
+	#[track_caller]
+	pub fn grow_one(&mut self) {
+		if let Err(err) = self.grow_amortized(self.cap, 1) {
+			handle_error(err);
+		}
+	}
+	/// Shrinks the buffer down to the specified capacity. If the given amount
+	/// is 0, actually completely deallocates.
+	///
+	/// # Panics
+	///
+	/// Panics if the given amount is *larger* than the current capacity,
+	/// or if `len` is larger than the given amount.
+	#[track_caller]
+	#[inline]
+	pub fn shrink_to_fit(&mut self, len: usize, cap: usize) {
+		assert!(cap <= self.capacity(), "Tried to shrink to a larger capacity");
+		assert!(len <= cap, "Tried to shrink below the length");
+		if T::LAYOUT.size() == 0 {
+			return;
+		}
+		if let Err(err) = self.set_capacity(cap) {
+			handle_error(err);
+		}
+	}
+	/// Copies `count` rows from `src_index` to `dst_index` within every column.
+	///
+	/// # Safety
+	///
+	/// Both ranges of rows must be within the capacity.
+	#[inline]
+	pub const unsafe fn copy_rows(&self, src_index: usize, dst_index: usize, count: usize) {
+		let mut index = 0;
+		while index < N {
+			let size = T::FIELDS.layout_of(index).size();
+			let column = self.field_ptr(index);
+			unsafe { core::ptr::copy(column.add(src_index * size), column.add(dst_index * size), count * size) };
+			index += 1;
+		}
+	}
+	/// Moves the first `count` rows of `src` into every column starting at `dst_index`.
+	///
+	/// # Safety
+	///
+	/// `src` must not be `self`, its first `count` rows must not be used again,
+	/// and `dst_index + count` must not exceed the capacity.
+	#[inline]
+	pub const unsafe fn move_rows(&self, src: &Self, dst_index: usize, count: usize) {
+		let mut index = 0;
+		while index < N {
+			let size = T::FIELDS.layout_of(index).size();
+			unsafe { core::ptr::copy_nonoverlapping(src.field_ptr(index), self.field_ptr(index).add(dst_index * size), count * size) };
+			index += 1;
+		}
+	}
+	/// Rotates the rows in `start..end` of every column so that the row at `mid` comes first.
+	///
+	/// # Safety
+	///
+	/// `start <= mid <= end`, and `end` must not exceed the capacity.
+	#[inline]
+	pub const unsafe fn rotate_rows(&self, start: usize, mid: usize, end: usize) {
+		let mut index = 0;
+		while index < N {
+			let size = T::FIELDS.layout_of(index).size();
+			unsafe { rotate_column(self.field_ptr(index), size, start, mid, end) };
+			index += 1;
+		}
+	}
+	/// # Safety
+	///
+	/// See [`Fields::retain`](crate::fields::Fields::retain).
+	#[inline]
+	pub unsafe fn retain(&self, len: &mut usize, keep: impl FnMut(usize) -> bool, drop_row: impl FnMut(usize)) {
+		unsafe { retain_rows(len, keep, drop_row, |src_index, dst_index, count| self.copy_rows(src_index, dst_index, count)) }
+	}
+	/// # Safety
+	///
+	/// See [`Fields::apply_permutation`](crate::fields::Fields::apply_permutation).
+	#[inline]
+	pub unsafe fn apply_permutation(&self, permutation: &mut [usize]) {
+		let columns = (0..N).map(|index| (self.field_ptr(index), T::FIELDS.layout_of(index).size()));
+		unsafe { permute_columns(columns, permutation) }
+	}
+	#[inline]
+	fn needs_to_grow(&self, len: usize, additional: usize) -> bool {
+		additional > self.capacity().wrapping_sub(len)
+	}
+	fn grow_amortized(&mut self, len: usize, additional: usize) -> Result<(), TryReserveError> {
+		// This is ensured by the calling contexts.
+		debug_assert!(additional > 0);
+
+		if T::LAYOUT.size() == 0 {
+			// Since we return a capacity of `usize::MAX` when the row is zero-sized,
+			// getting to here necessarily means the buffer is overfull.
+			return Err(CapacityOverflow.into());
+		}
+
+		let required_cap = len.checked_add(additional).ok_or(CapacityOverflow)?;
+		let cap = cmp::max(self.cap * 2, required_cap);
+		let cap = cmp::max(min_non_zero_cap(T::LAYOUT.size()), cap);
+		self.set_capacity(cap)
+	}
+	// Reallocates every column to hold `cap` rows. Every new column is allocated before
+	// the old ones are freed, so if one allocation fails the columns are left as they were.
+	#[cold]
+	fn set_capacity(&mut self, cap: usize) -> Result<(), TryReserveError> {
+		let old_cap = self.cap;
+		if old_cap == cap {
+			return Ok(());
+		}
+		let mut ptrs = Self::dangling();
+		if cap != 0 {
+			for index in 0..N {
+				let field = T::FIELDS.layout_of(index);
+				if field.size() == 0 {
+					continue;
+				}
+				let region = layout_column(cap, field).and_then(|layout| {
+					self.alloc.allocate(layout).map_err(|_| AllocError { layout }.into())
+				});
+				match region {
+					Ok(region) => ptrs[index] = region.cast(),
+					Err(err) => {
+						// SAFETY: the columns before index were just allocated for cap rows
+						unsafe { self.free_columns(&ptrs[..index], cap) };
+						return Err(err);
+					},
+				}
+			}
+		}
+		let rows = cmp::min(old_cap, cap);
+		for (index, ptr) in ptrs.iter().enumerate() {
+			let size = T::FIELDS.layout_of(index).size();
+			// SAFETY: both columns hold at least `rows` rows, and are separate allocations
+			unsafe { core::ptr::copy_nonoverlapping(self.ptrs[index].as_ptr(), ptr.as_ptr(), rows * size) };
+		}
+		// SAFETY: the old columns are allocated for old_cap rows, and their rows were moved out
+		unsafe { self.free_columns(&self.ptrs, old_cap) };
+		self.ptrs = ptrs;
+		self.cap = cap;
+		Ok(())
+	}
+	// SAFETY:
+	// the column at each index of ptrs must be allocated for cap rows, and must not be used again
+	unsafe fn free_columns(&self, ptrs: &[NonNull<u8>], cap: usize) {
+		if cap == 0 {
+			return;
+		}
+		for (index, &ptr) in ptrs.iter().enumerate() {
+			let field = T::FIELDS.layout_of(index);
+			if field.size() != 0 {
+				// SAFETY: Precondition passed to the caller
+				unsafe {
+					let layout = Layout::from_size_align_unchecked(field.size().unchecked_mul(cap), field.align());
+					self.alloc.deallocate(ptr, layout);
+				}
+			}
+		}
+	}
+}
+
+impl<const N:usize, T: StructInfo<N>, A: Allocator> Drop for RawColumns<N, T, A> {
+	/// Frees the memory owned by every column *without* trying to drop its contents.
+	fn drop(&mut self) {
+		// SAFETY: every column is allocated for self.cap rows
+		unsafe { self.free_columns(&self.ptrs, self.cap) }
+	}
+}
+
 impl<A: Allocator> RawColVecInner<A> {
 	#[inline]
-	const fn new_in(alloc: A, align: NonZero<usize>) -> Self {
+	pub(crate) const fn new_in(alloc: A, align: NonZero<usize>) -> Self {
 		let ptr = NonNull::without_provenance(align);
 		// `cap: 0` means "unallocated". zero-sized types are ignored.
 		Self { ptr, cap: 0, alloc }
 	}
 	#[inline]
 	#[track_caller]
-	fn with_capacity_in(capacity: usize, alloc: A, elem_layout: Layout) -> Self {
+	pub(crate) fn with_capacity_in(capacity: usize, alloc: A, elem_layout: Layout) -> Self {
 		match Self::try_allocate_in(capacity, AllocInit::Uninitialized, alloc, elem_layout) {
 			Ok(this) => {
 				unsafe {
@@ -173,7 +534,9 @@ impl<A: Allocator> RawColVecInner<A> {
 		elem_layout: Layout,
 	) -> Result<Self, TryReserveError> {
 		// capacity must be a multiple of alignment
-		let capacity = capacity.next_multiple_of(elem_layout.align());
+		let Some(capacity) = capacity.checked_next_multiple_of(elem_layout.align()) else {
+			return Err(CapacityOverflow.into());
+		};
 		// We avoid `unwrap_or_else` here because it bloats the amount of
 		// LLVM IR generated.
 		let layout = match layout_colvec(capacity, elem_layout) {
@@ -186,9 +549,7 @@ impl<A: Allocator> RawColVecInner<A> {
 			return Ok(Self::new_in(alloc, unsafe{NonZero::new_unchecked(elem_layout.align())}));
 		}
 
-		if let Err(err) = alloc_guard(layout.size()) {
-			return Err(err);
-		}
+		alloc_guard(layout.size())?;
 
 		let result = match init {
 			AllocInit::Uninitialized => alloc.allocate(layout),
@@ -209,27 +570,27 @@ impl<A: Allocator> RawColVecInner<A> {
 		})
 	}
 	#[inline]
-	const fn capacity(&self, elem_size: usize) -> usize {
+	pub(crate) const fn capacity(&self, elem_size: usize) -> usize {
 		if elem_size == 0 { usize::MAX } else { self.cap }
 	}
 	#[inline]
-	fn allocator(&self) -> &A {
+	pub(crate) fn allocator(&self) -> &A {
 		&self.alloc
 	}
 	#[inline]
 	#[track_caller]
-	fn reserve<const N:usize>(&mut self, len: usize, additional: usize, elem_layout: Layout, fields: &Fields<N>) {
+	pub(crate) fn reserve(&mut self, len: usize, additional: usize, elem_layout: Layout, fields: &[Field]) {
 		// Callers expect this function to be very cheap when there is already sufficient capacity.
 		// Therefore, we move all the resizing and error-handling logic from grow_amortized and
 		// handle_reserve behind a call, while making sure that this function is likely to be
 		// inlined as just a comparison and a call if the comparison fails.
 		#[cold]
-		fn do_reserve_and_handle<const N:usize, A: Allocator>(
+		fn do_reserve_and_handle<A: Allocator>(
 			slf: &mut RawColVecInner<A>,
 			len: usize,
 			additional: usize,
 			elem_layout: Layout,
-			fields: &Fields<N>,
+			fields: &[Field],
 		) {
 			if let Err(err) = slf.grow_amortized(len, additional, elem_layout, fields) {
 				handle_error(err);
@@ -240,9 +601,41 @@ impl<A: Allocator> RawColVecInner<A> {
 			do_reserve_and_handle(self, len, additional, elem_layout, fields);
 		}
 	}
+	fn try_reserve(
+		&mut self,
+		len: usize,
+		additional: usize,
+		elem_layout: Layout,
+		fields: &[Field],
+	) -> Result<(), TryReserveError> {
+		if self.needs_to_grow(len, additional, elem_layout) {
+			self.grow_amortized(len, additional, elem_layout, fields)?;
+		}
+		unsafe {
+			// Inform the optimizer that the reservation has succeeded or wasn't needed
+			hint::assert_unchecked(!self.needs_to_grow(len, additional, elem_layout));
+		}
+		Ok(())
+	}
+	fn try_reserve_exact(
+		&mut self,
+		len: usize,
+		additional: usize,
+		elem_layout: Layout,
+		fields: &[Field],
+	) -> Result<(), TryReserveError> {
+		if self.needs_to_grow(len, additional, elem_layout) {
+			self.grow_exact(len, additional, elem_layout, fields)?;
+		}
+		unsafe {
+			// Inform the optimizer that the reservation has succeeded or wasn't needed
+			hint::assert_unchecked(!self.needs_to_grow(len, additional, elem_layout));
+		}
+		Ok(())
+	}
 	#[inline]
 	#[track_caller]
-	fn grow_one<const N:usize>(&mut self, elem_layout: Layout, fields: &Fields<N>) {
+	pub(crate) fn grow_one(&mut self, elem_layout: Layout, fields: &[Field]) {
 		if let Err(err) = self.grow_amortized(self.cap, 1, elem_layout, fields) {
 			handle_error(err);
 		}
@@ -263,7 +656,7 @@ impl<A: Allocator> RawColVecInner<A> {
 			unsafe {
 				let alloc_size = elem_layout.size().unchecked_mul(self.cap);
 				let layout = Layout::from_size_align_unchecked(alloc_size, elem_layout.align());
-				Some((self.ptr.into(), layout))
+				Some((self.ptr, layout))
 			}
 		}
 	}
@@ -275,12 +668,12 @@ impl<A: Allocator> RawColVecInner<A> {
 		self.ptr = ptr.cast();
 		self.cap = cap;
 	}
-	fn grow_amortized<const N:usize>(
+	fn grow_amortized(
 		&mut self,
 		len: usize,
 		additional: usize,
 		elem_layout: Layout,
-		fields: &Fields<N>,
+		fields: &[Field],
 	) -> Result<(), TryReserveError> {
 		// This is ensured by the calling contexts.
 		debug_assert!(additional > 0);
@@ -300,7 +693,7 @@ impl<A: Allocator> RawColVecInner<A> {
 		let cap = cmp::max(min_non_zero_cap(elem_layout.size()), cap);
 		// cap must be a multiple of align due to using the unpadded elem_layout
 		// for the allocation layout calculation in `layout_colvec`.
-		let cap = cap.next_multiple_of(elem_layout.align());
+		let cap = cap.checked_next_multiple_of(elem_layout.align()).ok_or(CapacityOverflow)?;
 
 		let new_layout = layout_colvec(cap, elem_layout)?;
 
@@ -318,6 +711,92 @@ impl<A: Allocator> RawColVecInner<A> {
 		unsafe { self.set_ptr_and_cap(ptr, cap) };
 		Ok(())
 	}
+	fn grow_exact(
+		&mut self,
+		len: usize,
+		additional: usize,
+		elem_layout: Layout,
+		fields: &[Field],
+	) -> Result<(), TryReserveError> {
+		if elem_layout.size() == 0 {
+			// Since we return a capacity of `usize::MAX` when the type size is
+			// 0, getting to here necessarily means the `RawVec` is overfull.
+			return Err(CapacityOverflow.into());
+		}
+
+		let cap = len.checked_add(additional).ok_or(CapacityOverflow)?;
+		// cap must be a multiple of align due to using the unpadded elem_layout
+		// for the allocation layout calculation in `layout_colvec`.
+		let cap = cap.checked_next_multiple_of(elem_layout.align()).ok_or(CapacityOverflow)?;
+		let new_layout = layout_colvec(cap, elem_layout)?;
+
+		let ptr = finish_grow(
+			new_layout,
+			self.current_memory(elem_layout),
+			&mut self.alloc,
+			fields,
+			self.cap,
+			cap,
+			len,
+		)?;
+		// SAFETY: finish_grow would have resulted in a capacity overflow if we tried to allocate more than `isize::MAX` items
+		unsafe {
+			self.set_ptr_and_cap(ptr, cap);
+		}
+		Ok(())
+	}
+	#[inline]
+	fn shrink(&mut self, len: usize, cap: usize, elem_layout: Layout, fields: &[Field]) -> Result<(), TryReserveError> {
+		assert!(cap <= self.capacity(elem_layout.size()), "Tried to shrink to a larger capacity");
+		assert!(len <= cap, "Tried to shrink below the length");
+		// SAFETY: Just checked this isn't trying to grow
+		unsafe { self.shrink_unchecked(len, cap, elem_layout, fields) }
+	}
+	/// # Safety
+	/// `cap` must be less than or equal to `self.capacity(elem_layout.size())`
+	/// `len` must be less than or equal to `cap`
+	unsafe fn shrink_unchecked(
+		&mut self,
+		len: usize,
+		cap: usize,
+		elem_layout: Layout,
+		fields: &[Field],
+	) -> Result<(), TryReserveError> {
+		let (ptr, layout) =
+			if let Some(mem) = self.current_memory(elem_layout) { mem } else { return Ok(()) };
+
+		// cap must be a multiple of align due to using the unpadded elem_layout
+		// for the allocation layout calculation in `layout_colvec`.
+		// This cannot overflow because self.cap is already a multiple of align.
+		let cap = cap.next_multiple_of(elem_layout.align());
+		if cap == self.cap {
+			return Ok(());
+		}
+
+		if cap == 0 {
+			unsafe { self.alloc.deallocate(ptr, layout) };
+			self.ptr = NonNull::without_provenance(unsafe { NonZero::new_unchecked(elem_layout.align()) });
+			self.cap = 0;
+		} else {
+			let new_layout = unsafe {
+				let new_size = elem_layout.size().unchecked_mul(cap);
+				Layout::from_size_align_unchecked(new_size, layout.align())
+			};
+			// the columns have to be packed into the front of the allocation before it is truncated
+			unsafe { shrink_columns(fields, ptr.as_ptr(), self.cap, cap, len) };
+			let memory = unsafe { self.alloc.shrink(ptr, layout, new_layout) };
+			let Ok(region) = memory else{
+				// the old allocation is untouched, so the columns are moved back where they came from
+				unsafe { grow_columns(fields, ptr.as_ptr(), cap, self.cap, len) };
+				return Err(AllocError { layout: new_layout }.into());
+			};
+			// SAFETY: if the allocation is valid, then the capacity is too
+			unsafe {
+				self.set_ptr_and_cap(region, cap);
+			}
+		}
+		Ok(())
+	}
 	/// # Safety
 	///
 	/// This function deallocates the owned allocation, but does not update `ptr` or `cap` to
@@ -325,7 +804,7 @@ impl<A: Allocator> RawColVecInner<A> {
 	/// after this function returns.
 	/// Ideally this function would take `self` by move, but it cannot because it exists to be
 	/// called from a `Drop` impl.
-	unsafe fn deallocate(&mut self, elem_layout: Layout) {
+	pub(crate) unsafe fn deallocate(&mut self, elem_layout: Layout) {
 		if let Some((ptr, layout)) = self.current_memory(elem_layout) {
 			unsafe {
 				self.alloc.deallocate(ptr, layout);
@@ -337,11 +816,11 @@ impl<A: Allocator> RawColVecInner<A> {
 // not marked inline(never) since we want optimizers to be able to observe the specifics of this
 // function, see tests/codegen/vec-reserve-extend.rs.
 #[cold]
-fn finish_grow<const N:usize,A>(
+fn finish_grow<A>(
 	new_layout: Layout,
 	current_memory: Option<(NonNull<u8>, Layout)>,
 	alloc: &mut A,
-	fields: &Fields<N>,
+	fields: &[Field],
 	old_capacity: usize,
 	new_capacity: usize,
 	len: usize,
@@ -353,17 +832,18 @@ where
 
 	if let Some((ptr, old_layout)) = current_memory {
 		debug_assert_eq!(old_layout.align(), new_layout.align());
-		let memory = unsafe {
-			// The allocator checks for alignment equality
-			hint::assert_unchecked(old_layout.align() == new_layout.align());
-			alloc.grow(ptr, old_layout, new_layout)
-		};
-		let Ok(region) = memory else{
+		// The allocator cannot be asked whether it would extend the block in place, and when
+		// `grow` moves the block it copies the whole old allocation before every column but the
+		// first is moved again. Copy each column straight to its final offset in a fresh
+		// allocation instead, which only copies the initialized rows once. An allocator which could
+		// have extended the block in place still briefly holds both blocks, see "Storage" in the README.
+		let Ok(region) = alloc.allocate(new_layout) else{
 			return Err(AllocError { layout: new_layout }.into());
 		};
-
-		unsafe{ fields.grow_fields(ptr.as_ptr(), old_capacity, new_capacity, len) }
-
+		unsafe {
+			move_columns(fields, ptr.as_ptr(), region.cast::<u8>().as_ptr(), old_capacity, new_capacity, 0, len);
+			alloc.deallocate(ptr, old_layout);
+		}
 		Ok(region)
 	} else {
 		alloc.allocate(new_layout)
@@ -410,6 +890,13 @@ const fn repeat_packed(layout: &Layout, n: usize) -> Result<Layout, LayoutError>
 	}
 }
 
+#[inline]
+fn layout_column(cap: usize, field: Layout) -> Result<Layout, TryReserveError> {
+	// the safe constructor enforces the isize size limit
+	let size = field.size().checked_mul(cap).ok_or(CapacityOverflow)?;
+	Layout::from_size_align(size, field.align()).map_err(|_| CapacityOverflow.into())
+}
+
 #[inline]
 fn layout_colvec(cap: usize, elem_layout: Layout) -> Result<Layout, TryReserveError> {
 	repeat_packed(&elem_layout, cap).map_err(|_| CapacityOverflow.into())
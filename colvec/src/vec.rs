@@ -0,0 +1,3 @@
+// the alloc crate can not be named from generated code without an extern crate in the user's crate
+extern crate alloc;
+pub use alloc::vec::Vec;
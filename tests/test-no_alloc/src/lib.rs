@@ -0,0 +1,121 @@
+#![no_std]
+
+#[cfg(test)]
+mod tests {
+	use ::colvec::*;
+	use core::sync::atomic::{AtomicUsize,Ordering};
+
+	extern crate std;
+
+	/// Counts how many times it has been dropped.
+	#[derive(Clone)]
+	struct DropCounter(&'static AtomicUsize);
+	impl Drop for DropCounter {
+		fn drop(&mut self) {
+			self.0.fetch_add(1, Ordering::Relaxed);
+		}
+	}
+
+	#[derive(ColVec,Debug,Clone,PartialEq)]
+	struct Row{
+		id:u8,
+		position:[f32;3],
+		flag:bool,
+		count:u64,
+	}
+	fn row(id:u8)->Row{
+		Row{id,position:[id as f32;3],flag:id.is_multiple_of(2),count:id as u64*1000}
+	}
+
+	#[test]
+	fn test_array_colvec() {
+		// a capacity which is not a multiple of the row's alignment
+		let mut rows=RowArrayColVec::<3>::new();
+		assert_eq!(3, rows.capacity());
+		for id in 0..3 {
+			rows.push(row(id));
+		}
+		assert!(rows.is_full());
+		assert_eq!(Err(row(3)), rows.try_push(row(3)));
+		assert_eq!(&[0,1,2], rows.id_slice());
+		assert_eq!(&[[0.0;3],[1.0;3],[2.0;3]], rows.position_slice());
+		assert_eq!(&[true,false,true], rows.flag_slice());
+		assert_eq!(&[0,1000,2000], rows.count_slice());
+
+		assert_eq!(Some(row(2)), rows.pop());
+		assert_eq!(row(0), rows.swap_remove(0));
+		*rows.get_mut(0).unwrap().count=5;
+		rows.columns_mut().flag.fill(true);
+		assert_eq!(Some(&5), rows.get(0).map(|row|row.count));
+		assert_eq!(&[true], rows.columns().flag);
+		assert_eq!(Some(&[1.0;3]), rows.iter().map(|row|row.position).next());
+
+		let cloned=rows.clone();
+		assert_eq!(std::format!("{cloned:?}"), std::format!("{rows:?}"));
+		rows.clear();
+		assert!(rows.is_empty());
+		rows.extend([row(7),row(8)]);
+		assert_eq!(&[7,8], rows.id_slice());
+	}
+
+	#[test]
+	fn test_array_colvec_layout() {
+		// every column is aligned and none of them overlap
+		fn check<const CAP:usize>(){
+			let mut rows=RowArrayColVec::<CAP>::new();
+			for id in 0..CAP as u8 {
+				rows.push(row(id));
+			}
+			for (id,row) in (0..).zip(rows.iter()) {
+				assert_eq!(self::row(id), Row{id:*row.id,position:*row.position,flag:*row.flag,count:*row.count});
+			}
+			let columns=rows.columns();
+			assert!(columns.position.as_ptr().is_aligned());
+			assert!(columns.count.as_ptr().is_aligned());
+		}
+		check::<1>();
+		check::<2>();
+		check::<3>();
+		check::<5>();
+		check::<8>();
+	}
+
+	#[test]
+	fn test_array_colvec_generic() {
+		#[derive(ColVec)]
+		struct Pair<T>{
+			first:T,
+			second:u8,
+			marker:(),
+		}
+		let mut pairs=PairArrayColVec::<u16,3>::new();
+		pairs.push(Pair{first:1,second:2,marker:()});
+		pairs.push(Pair{first:3,second:4,marker:()});
+		assert_eq!(&[1,3], pairs.first_slice());
+		assert_eq!(&[2,4], pairs.second_slice());
+		assert_eq!(&[(),()], pairs.marker_slice());
+	}
+
+	#[test]
+	fn test_array_colvec_drop() {
+		#[derive(ColVec,Clone)]
+		struct Droppy{
+			id:u8,
+			counter:DropCounter,
+		}
+		static COUNT:AtomicUsize=AtomicUsize::new(0);
+		let droppy=|id|Droppy{id,counter:DropCounter(&COUNT)};
+		let mut droppies=DroppyArrayColVec::<7>::default();
+		for id in 0..6 {
+			droppies.push(droppy(id));
+		}
+		droppies.truncate(4);
+		assert_eq!(2, COUNT.load(Ordering::Relaxed));
+		let cloned=droppies.clone();
+		drop(droppies);
+		assert_eq!(2+4, COUNT.load(Ordering::Relaxed));
+		assert_eq!(&[0,1,2,3], cloned.id_slice());
+		drop(cloned);
+		assert_eq!(2+4+4, COUNT.load(Ordering::Relaxed));
+	}
+}